@@ -0,0 +1,206 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+const AUTOSAVE_KEY: &str = "celestialsphere-autosave";
+/// Quiet time after the last change before the diagram is written
+const AUTOSAVE_DELAY_MS: u32 = 1000;
+/// A session keeps updating its latest snapshot for this long before starting a new one
+const SNAPSHOT_INTERVAL_MS: f64 = 5.0 * 60.0 * 1000.0;
+const MAX_SNAPSHOTS: usize = 5;
+
+/// Autosaved copy of the diagram
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Start time of the page session that wrote the snapshot
+    pub session: f64,
+    /// When the snapshot was first and last written, in milliseconds since the epoch
+    pub started: f64,
+    pub time: f64,
+    pub point_count: usize,
+    /// The diagram as written by `SaveFile::to_json`, kept as plain JSON so that
+    /// snapshots of older formats are migrated like files when restored
+    pub data: serde_json::Value,
+}
+
+/// Autosaved snapshots, newest first
+pub fn load_snapshots() -> Vec<Snapshot> {
+    platform::storage_get(AUTOSAVE_KEY)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn store_snapshots(snapshots: &[Snapshot]) -> Result<(), String> {
+    let json = serde_json::to_string(snapshots).map_err(|e| e.to_string())?;
+    platform::storage_set(AUTOSAVE_KEY, &json)
+}
+
+/// Adds `snapshot` to the list, replacing the latest one if it belongs to the same
+/// session and is recent enough
+fn record_snapshot(snapshots: &mut Vec<Snapshot>, mut snapshot: Snapshot) {
+    match snapshots.first_mut() {
+        Some(latest)
+            if latest.session == snapshot.session
+                && snapshot.time - latest.started < SNAPSHOT_INTERVAL_MS =>
+        {
+            snapshot.started = latest.started;
+            *latest = snapshot;
+        }
+        _ => snapshots.insert(0, snapshot),
+    }
+    snapshots.truncate(MAX_SNAPSHOTS);
+}
+
+fn write_snapshot(snapshot: Snapshot) {
+    let mut snapshots = load_snapshots();
+    if snapshots
+        .first()
+        .is_some_and(|latest| latest.data == snapshot.data)
+    {
+        return;
+    }
+    record_snapshot(&mut snapshots, snapshot);
+    // Older snapshots give way when the storage quota is reached
+    while let Err(error) = store_snapshots(&snapshots) {
+        if snapshots.len() <= 1 {
+            platform::log_error(&format!("Autosave failed: {error}"));
+            return;
+        }
+        snapshots.pop();
+    }
+}
+
+pub fn clear_snapshots() {
+    platform::storage_remove(AUTOSAVE_KEY);
+}
+
+/// Writes the diagram to storage shortly after it stops changing. Nothing is
+/// written while `paused`, so that the snapshots of the previous session survive
+/// until the user decided whether to restore one.
+pub fn use_autosave(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+    paused: Signal<bool>,
+) {
+    let session = use_hook(platform::now_ms);
+    let mut generation = use_signal(|| 0usize);
+
+    use_effect(move || {
+        // Subscribes to every part of the diagram
+        let _ = (
+            points.read(),
+            arcs.read(),
+            great_circles.read(),
+            small_circles.read(),
+            state.read(),
+        );
+        *generation.write() += 1;
+        let current = *generation.peek();
+
+        spawn(async move {
+            platform::sleep_ms(AUTOSAVE_DELAY_MS).await;
+            if *generation.peek() != current || *paused.peek() {
+                return;
+            }
+            let save_file = SaveFile::new(
+                &points.peek(),
+                &arcs.peek(),
+                &great_circles.peek(),
+                &small_circles.peek(),
+                &state.peek(),
+            );
+            // An untouched new diagram is not worth a snapshot
+            let started = load_snapshots()
+                .first()
+                .is_some_and(|latest| latest.session == session);
+            if save_file.points.is_empty() && !started {
+                return;
+            }
+            let Ok(data) = serde_json::to_value(&save_file) else {
+                return;
+            };
+            let time = platform::now_ms();
+            write_snapshot(Snapshot {
+                session,
+                started: time,
+                time,
+                point_count: save_file.points.len(),
+                data,
+            });
+        });
+    });
+}
+
+/// Lists the autosaved snapshots and restores one of them. Shown on startup when
+/// snapshots exist and from the file panel.
+#[component]
+pub fn AutosaveDialog(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+    mut show: Signal<bool>,
+) -> Element {
+    let mut snapshots = use_signal(load_snapshots);
+    let mut error = use_signal(|| None::<String>);
+    // Repairs the restored snapshot needed, shown in place of the list
+    let mut repaired = use_signal(|| None::<LoadReport>);
+    if let Some(report) = repaired() {
+        return rsx! {
+            LoadReportDialog { report, on_close: move |_| show.set(false) }
+        };
+    }
+
+    rsx! {
+        div { class: "dialog-backdrop",
+            div { class: "dialog", role: "dialog",
+                h3 { "Restore an autosaved diagram?" }
+                if snapshots.read().is_empty() {
+                    p { "There are no autosaved diagrams." }
+                } else {
+                    p { "The diagram is saved on this device as you work. Recent versions:" }
+                }
+                ul { class: "snapshot-list",
+                    for snapshot in snapshots() {
+                        li {
+                            span { "{platform::format_time(snapshot.time)} — {snapshot.point_count} points" }
+                            button {
+                                onclick: move |_| {
+                                    match restore_data_from_json(
+                                        &snapshot.data.to_string(),
+                                        points,
+                                        arcs,
+                                        great_circles,
+                                        small_circles,
+                                        state,
+                                    ) {
+                                        Ok(repairs) if repairs.is_empty() => show.set(false),
+                                        Ok(repairs) => repaired.set(Some(LoadReport::Repaired(repairs))),
+                                        Err(message) => error.set(Some(message)),
+                                    }
+                                },
+                                "Restore"
+                            }
+                        }
+                    }
+                }
+                if let Some(message) = error() {
+                    p { class: "dialog-error", "{message}" }
+                }
+                button { onclick: move |_| show.set(false), "Close" }
+                if !snapshots.read().is_empty() {
+                    button {
+                        onclick: move |_| {
+                            clear_snapshots();
+                            snapshots.set(Vec::new());
+                        },
+                        "Delete all"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Renders a saved diagram to SVG or PNG without opening the app:
+//!
+//! ```sh
+//! cargo run --bin render -- diagram.json -o diagram.svg -o diagram.png --euler 30,0,15
+//! ```
+
+use celestialsphere::*;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: render <diagram.json> [options]
+
+Options:
+  -o, --output <file>        Image to write, .svg or .png; may be repeated
+                             (default: the diagram's name with .svg)
+      --euler <x,y,z>        View rotation in degrees, as set by the rotation sliders
+      --quaternion <w,x,y,z> View orientation as a quaternion
+      --projection <name>    Projection, such as orthographic or stereographic
+      --width <px>           Width of PNG images (default 1200)
+      --dpi <dpi>            Resolution recorded in PNG images (default 150)
+      --transparent          Transparent PNG background
+      --white                White background, for printing
+  -h, --help                 Show this help";
+
+/// Image formats the renderer writes, chosen by the file extension
+enum Format {
+    Svg,
+    Png,
+}
+
+struct Options {
+    input: PathBuf,
+    outputs: Vec<(PathBuf, Format)>,
+    view: Option<Quaternion>,
+    projection: Option<Projection>,
+    png: PngOptions,
+    white: bool,
+}
+
+/// Numbers separated by commas, as in `30,0,15`
+fn numbers<const N: usize>(text: &str, option: &str) -> Result<[f64; N], String> {
+    let values: Vec<f64> = text
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("{option} expects numbers separated by commas, not `{text}`"))?;
+    values
+        .try_into()
+        .map_err(|_| format!("{option} expects {N} numbers separated by commas"))
+}
+
+fn format_of(path: &Path) -> Result<Format, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("svg") => Ok(Format::Svg),
+        Some("png") => Ok(Format::Png),
+        _ => Err(format!(
+            "Cannot tell the format of {}; use a .svg or .png file name",
+            path.display()
+        )),
+    }
+}
+
+fn parse_options(args: impl IntoIterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut args = args.into_iter();
+    let mut input = None;
+    let mut outputs = Vec::new();
+    let mut options = Options {
+        input: PathBuf::new(),
+        outputs: Vec::new(),
+        view: None,
+        projection: None,
+        png: PngOptions::default(),
+        white: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} expects a value"));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => outputs.push(PathBuf::from(value()?)),
+            "--euler" => {
+                let euler = numbers(&value()?, &arg)?;
+                options.view = Some(Quaternion::from_euler_deg(euler));
+            }
+            "--quaternion" => {
+                let [w, x, y, z] = numbers(&value()?, &arg)?;
+                let q = Quaternion::new(w, x, y, z);
+                if !q.is_finite() || w * w + x * x + y * y + z * z < 1e-12 {
+                    return Err("--quaternion must not be zero".to_string());
+                }
+                options.view = Some(q.normalize());
+            }
+            "--projection" => {
+                let name = value()?.to_lowercase();
+                let projection = Projection::ALL
+                    .into_iter()
+                    .find(|projection| projection.name().to_lowercase().starts_with(&name))
+                    .ok_or_else(|| {
+                        let names: Vec<&str> = Projection::ALL.iter().map(|p| p.name()).collect();
+                        format!(
+                            "Unknown projection `{name}`; choose from {}",
+                            names.join(", ")
+                        )
+                    })?;
+                options.projection = Some(projection);
+            }
+            "--width" => {
+                let width = value()?;
+                options.png.width = width
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|width| (16..=8000).contains(width))
+                    .ok_or_else(|| format!("--width expects 16 to 8000 pixels, not `{width}`"))?;
+            }
+            "--dpi" => {
+                let dpi = value()?;
+                options.png.dpi = dpi
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|dpi| *dpi >= 1.0)
+                    .ok_or_else(|| format!("--dpi expects a number of at least 1, not `{dpi}`"))?;
+            }
+            "--transparent" => options.png.transparent = true,
+            "--white" => options.white = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {arg}")),
+        }
+    }
+
+    options.input = input.ok_or("No diagram file given")?;
+    if outputs.is_empty() {
+        outputs.push(options.input.with_extension("svg"));
+    }
+    for output in outputs {
+        let format = format_of(&output)?;
+        options.outputs.push((output, format));
+    }
+    Ok(Some(options))
+}
+
+fn render(options: &Options) -> Result<(), String> {
+    let text = std::fs::read_to_string(&options.input)
+        .map_err(|error| format!("Cannot read {}: {error}", options.input.display()))?;
+    let mut save_file = SaveFile::from_json(&text)?;
+    for repair in save_file.validate()? {
+        eprintln!("warning: {repair}");
+    }
+    if let Some(view) = options.view {
+        save_file.view.quaternion = view;
+    }
+    if let Some(projection) = options.projection {
+        save_file.view.projection = projection;
+    }
+
+    let mut scene = save_file.scene();
+    if options.white {
+        scene = scene.printable();
+    }
+    for (path, format) in &options.outputs {
+        let bytes = match format {
+            Format::Svg => scene_to_svg(&scene).into_bytes(),
+            Format::Png => scene_to_png(&scene, &options.png)?,
+        };
+        std::fs::write(path, bytes)
+            .map_err(|error| format!("Cannot write {}: {error}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match render(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
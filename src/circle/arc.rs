@@ -1,7 +1,12 @@
 use crate::*;
 
 #[component]
-pub fn ArcDrawer(arcs: Signal<Vec<(usize, usize)>>, points: Signal<Vec<Point>>) -> Element {
+pub fn ArcDrawer(
+    arcs: Signal<Vec<(usize, usize)>>,
+    points: Signal<Vec<Point>>,
+    state: Signal<State>,
+) -> Element {
+    let projection = state.read().projection;
     let calculate_great_circle_arc = |p1: Vec3, p2: Vec3| -> Vec<Vec3> {
         let mut arc_points = Vec::new();
         let pq = (p1[0] * p2[0] + p1[1] * p2[1] + p1[2] * p2[2]).clamp(-1.0, 1.0);
@@ -37,28 +42,7 @@ pub fn ArcDrawer(arcs: Signal<Vec<(usize, usize)>>, points: Signal<Vec<Point>>)
                 let p1 = points()[p1_idx].rotated;
                 let p2 = points()[p2_idx].rotated;
                 let arc_points = calculate_great_circle_arc(p1, p2);
-                let mut front_path = Vec::new();
-                let mut back_path = Vec::new();
-                for [x, y, z] in arc_points {
-                    let svg_x = x * 25.0 + 50.0;
-                    let svg_y = y * 25.0 + 50.0;
-                    if z >= 0.0 {
-                        front_path.push(format!("{},{}", svg_x, svg_y));
-                    } else {
-                        back_path.push(format!("{},{}", svg_x, svg_y));
-                    }
-                }
-                let front_path_data = if !front_path.is_empty() {
-                    "M ".to_string() + &front_path.join(" L ")
-                } else {
-                    String::new()
-                };
-                let back_path_data = if !back_path.is_empty() {
-                    "M ".to_string() + &back_path.join(" L ")
-                } else {
-                    String::new()
-                };
-                (front_path_data, back_path_data)
+                projection.view_paths(&arc_points)
             })
         {
             path {
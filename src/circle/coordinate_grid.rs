@@ -1,173 +1,243 @@
 use crate::*;
+use serde::{Deserialize, Serialize};
 
-#[component]
-pub fn CoordinateGrid(state: Signal<State>) -> Element {
-    let mut lat_lines = Vec::new();
-    let mut lon_lines = Vec::new();
-    let rotation = state.read().quaternion;
-
-    for i in 0..12 {
-        let phi = i as f64 * std::f64::consts::TAU / 12.0;
-        let mut points = Vec::new();
-        for j in 0..=60 {
-            let theta = j as f64 * std::f64::consts::PI / 60.0 - std::f64::consts::FRAC_PI_2;
-            let x = phi.cos() * theta.cos();
-            let y = phi.sin() * theta.cos();
-            let z = theta.sin();
-            let rotated = rotation.rotate_point_active([x, y, z]);
-            points.push(rotated);
+/// Number of segments used to draw a full grid line
+const GRID_LINE_STEPS: usize = 72;
+
+/// Layout and styling of the coordinate grid, shared by the live view and SVG export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GridConfig {
+    /// Degrees between parallels
+    pub lat_step: f64,
+    /// Degrees between meridians
+    pub lon_step: f64,
+    pub lat_min: f64,
+    pub lat_max: f64,
+    pub lon_min: f64,
+    pub lon_max: f64,
+    /// Degree values along the equator and the first meridian
+    pub show_labels: bool,
+    pub color: String,
+    /// Opacity of the front side; the back side is drawn fainter
+    pub opacity: f64,
+    pub dashed: bool,
+    /// Pole of the great circle used as the grid's equator, `None` for the absolute frame
+    pub reference: Option<usize>,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            lat_step: 30.0,
+            lon_step: 30.0,
+            lat_min: -90.0,
+            lat_max: 90.0,
+            lon_min: 0.0,
+            lon_max: 360.0,
+            show_labels: false,
+            color: "#6B8E23".to_string(),
+            opacity: 0.3,
+            dashed: true,
+            reference: None,
         }
-        lon_lines.push(points);
     }
+}
+
+/// Point on the unit sphere at a grid latitude and longitude, in degrees
+pub fn grid_point(lat: f64, lon: f64) -> Vec3 {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [lon.cos() * lat.cos(), lon.sin() * lat.cos(), lat.sin()]
+}
 
-    for i in -5..=5 {
-        if i == 0 {
-            continue;
+/// Multiples of `step` within `[min, max]`, nudged off the ends by `skip_ends`
+fn grid_values(min: f64, max: f64, step: f64, skip_ends: bool) -> Vec<f64> {
+    let step = step.max(1.0);
+    let mut values = Vec::new();
+    let mut value = (min / step).ceil() * step;
+    while value <= max + 1e-9 {
+        if !skip_ends || (value > min + 1e-9 && value < max - 1e-9) {
+            values.push(value);
         }
-        let theta = i as f64 * std::f64::consts::FRAC_PI_6;
-        let mut points = Vec::new();
-        for j in 0..=60 {
-            let phi = j as f64 * std::f64::consts::TAU / 60.0;
-            let x = phi.cos() * theta.cos();
-            let y = phi.sin() * theta.cos();
-            let z = theta.sin();
-            let rotated = rotation.rotate_point_active([x, y, z]);
-            points.push(rotated);
+        value += step;
+    }
+    values
+}
+
+/// Samples `[from, to]` evenly, with a resolution relative to a full turn
+fn sample_range(from: f64, to: f64) -> Vec<f64> {
+    let steps = ((to - from).abs() / 360.0 * GRID_LINE_STEPS as f64)
+        .ceil()
+        .max(1.0) as usize;
+    (0..=steps)
+        .map(|i| from + (to - from) * i as f64 / steps as f64)
+        .collect()
+}
+
+impl GridConfig {
+    /// Rotation from grid coordinates (poles on the z axis) to absolute coordinates
+    pub fn frame(&self, points: &[Point]) -> Quaternion {
+        match self.reference.and_then(|pole| points.get(pole)) {
+            Some(pole) => Quaternion::from_two_vectors([0.0, 0.0, 1.0], pole.absolute),
+            None => Quaternion::identity(),
         }
-        lat_lines.push(points);
     }
 
-    let mut equator = Vec::new();
-    for j in 0..=60 {
-        let phi = j as f64 * std::f64::consts::TAU / 60.0;
-        let point = [phi.cos(), phi.sin(), 0.0];
-        let rotated = rotation.rotate_point_active(point);
-        equator.push(rotated);
+    /// Grid latitude and longitude, in degrees, of an absolute position
+    pub fn frame_coordinates(&self, points: &[Point], absolute: Vec3) -> [f64; 2] {
+        let [x, y, z] = self.frame(points).rotate_point_passive(absolute);
+        let lat = z.clamp(-1.0, 1.0).asin().to_degrees();
+        let lon = y.atan2(x).to_degrees();
+        [lat, if lon < 0.0 { lon + 360.0 } else { lon }]
     }
-    lat_lines.push(equator);
-
-    let transform_to_paths = |points: &Vec<Vec3>| {
-        let mut front_segments = Vec::new();
-        let mut back_segments = Vec::new();
-        let mut current_front = Vec::new();
-        let mut current_back = Vec::new();
-        let mut last_z_positive = None;
-        for i in 0..points.len() {
-            let [x, y, z] = points[i];
-            let z_positive = z >= 0.0;
-            let svg_x = x * 25.0 + 50.0;
-            let svg_y = y * 25.0 + 50.0;
-            if i == 0 {
-                if z_positive {
-                    current_front.push(format!("{},{}", svg_x, svg_y));
-                } else {
-                    current_back.push(format!("{},{}", svg_x, svg_y));
-                }
-                last_z_positive = Some(z_positive);
-                continue;
-            }
-            if let Some(was_positive) = last_z_positive {
-                if was_positive != z_positive {
-                    let prev = points[i - 1];
-                    let t = prev[2] / (prev[2] - z);
-                    let ix = prev[0] + t * (x - prev[0]);
-                    let iy = prev[1] + t * (y - prev[1]);
-                    let isvg_x = ix * 25.0 + 50.0;
-                    let isvg_y = iy * 25.0 + 50.0;
-                    if was_positive {
-                        current_front.push(format!("{},{}", isvg_x, isvg_y));
-                        front_segments.push(current_front);
-                        current_front = Vec::new();
-                        current_back.push(format!("{},{}", isvg_x, isvg_y));
-                    } else {
-                        current_back.push(format!("{},{}", isvg_x, isvg_y));
-                        back_segments.push(current_back);
-                        current_back = Vec::new();
-                        current_front.push(format!("{},{}", isvg_x, isvg_y));
-                    }
-                }
-            }
-            if z_positive {
-                current_front.push(format!("{},{}", svg_x, svg_y));
-            } else {
-                current_back.push(format!("{},{}", svg_x, svg_y));
-            }
-            last_z_positive = Some(z_positive);
+
+    /// Absolute position of a grid latitude and longitude, in degrees
+    pub fn frame_position(&self, points: &[Point], [lat, lon]: [f64; 2]) -> Vec3 {
+        let (lat, lon) = (lat.to_radians(), lon.to_radians());
+        self.frame(points).rotate_point_active([
+            lat.cos() * lon.cos(),
+            lat.cos() * lon.sin(),
+            lat.sin(),
+        ])
+    }
+
+    /// Fixes the reference after point `removed` was deleted with `swap_remove`,
+    /// which moved the point at `moved_from` into its slot
+    pub fn fix_reference_index(&mut self, removed: usize, moved_from: usize) {
+        if self.reference == Some(removed) {
+            self.reference = None;
+        } else if self.reference == Some(moved_from) {
+            self.reference = Some(removed);
         }
-        if !current_front.is_empty() {
-            front_segments.push(current_front);
+    }
+
+    fn full_longitude(&self) -> bool {
+        self.lon_max - self.lon_min >= 360.0 - 1e-9
+    }
+
+    pub fn parallels(&self) -> Vec<f64> {
+        grid_values(
+            self.lat_min.max(-90.0),
+            self.lat_max.min(90.0),
+            self.lat_step,
+            false,
+        )
+        .into_iter()
+        .filter(|lat| lat.abs() < 90.0 - 1e-9)
+        .collect()
+    }
+
+    pub fn meridians(&self) -> Vec<f64> {
+        let mut meridians = grid_values(self.lon_min, self.lon_max, self.lon_step, false);
+        // Both ends of a full turn are the same meridian
+        if self.full_longitude() {
+            meridians.retain(|&lon| lon < self.lon_min + 360.0 - 1e-9);
         }
-        if !current_back.is_empty() {
-            back_segments.push(current_back);
+        meridians
+    }
+
+    /// Grid lines as polylines in absolute coordinates
+    pub fn lines(&self) -> Vec<Vec<Vec3>> {
+        let mut lines = Vec::new();
+        let longitudes = sample_range(self.lon_min, self.lon_max);
+        for lat in self.parallels() {
+            lines.push(longitudes.iter().map(|&lon| grid_point(lat, lon)).collect());
         }
-        let front_data = front_segments
-            .iter()
-            .filter(|segment| !segment.is_empty())
-            .map(|segment| "M ".to_string() + &segment.join(" L "))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let back_data = back_segments
-            .iter()
-            .filter(|segment| !segment.is_empty())
-            .map(|segment| "M ".to_string() + &segment.join(" L "))
-            .collect::<Vec<_>>()
-            .join(" ");
-        (front_data, back_data)
-    };
+        let latitudes = sample_range(self.lat_min.max(-90.0), self.lat_max.min(90.0));
+        for lon in self.meridians() {
+            lines.push(latitudes.iter().map(|&lat| grid_point(lat, lon)).collect());
+        }
+        lines
+    }
+
+    /// Degree labels: latitudes along the first meridian and longitudes along
+    /// the equator (or the lowest parallel if the equator is out of range)
+    pub fn labels(&self) -> Vec<(Vec3, String)> {
+        if !self.show_labels {
+            return vec![];
+        }
+        let mut labels = Vec::new();
+        let label_lon = self.lon_min;
+        for lat in self.parallels() {
+            labels.push((grid_point(lat, label_lon), format!("{lat}°")));
+        }
+        let label_lat = 0.0f64.clamp(self.lat_min.max(-89.0), self.lat_max.min(89.0));
+        for lon in self.meridians() {
+            labels.push((grid_point(label_lat, lon), format!("{lon}°")));
+        }
+        labels
+    }
+}
+
+#[component]
+pub fn CoordinateGrid(state: Signal<State>, points: Signal<Vec<Point>>) -> Element {
+    let config = state.read().grid.clone();
+    let projection = state.read().projection;
+    let rotation = state
+        .read()
+        .quaternion
+        .multiply(config.frame(&points.read()));
+    let lines = config
+        .lines()
+        .into_iter()
+        .map(|line| {
+            line.into_iter()
+                .map(|p| rotation.rotate_point_active(p))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let labels = config
+        .labels()
+        .into_iter()
+        .filter_map(|(p, text)| {
+            let p = rotation.rotate_point_active(p);
+            let (x, y) = projection.view_position(p)?;
+            Some((x, y, projection.is_front(p), text))
+        })
+        .collect::<Vec<_>>();
+    let dasharray = if config.dashed { "0.5 0.5" } else { "none" };
+    let back_opacity = config.opacity / 3.0;
 
     rsx! {
-        for (i , front_path , back_path) in lat_lines
+        for (i , front_path , back_path) in lines
             .iter()
             .enumerate()
             .map(|(i, points)| {
-                let (front_path, back_path) = transform_to_paths(points);
+                let (front_path, back_path) = projection.view_paths(points);
                 (i, front_path, back_path)
             })
         {
             path {
-                key: "lat-front-{i}",
+                key: "grid-front-{i}",
                 d: front_path,
-                stroke: "#6B8E23",
+                stroke: "{config.color}",
                 stroke_width: "0.15",
-                stroke_dasharray: "0.5 0.5",
+                stroke_dasharray: dasharray,
                 fill: "none",
-                opacity: "0.3",
+                opacity: "{config.opacity}",
             }
             path {
-                key: "lat-back-{i}",
+                key: "grid-back-{i}",
                 d: back_path,
-                stroke: "#6B8E23",
+                stroke: "{config.color}",
                 stroke_width: "0.15",
-                stroke_dasharray: "0.5 0.5",
+                stroke_dasharray: dasharray,
                 fill: "none",
-                opacity: "0.1",
+                opacity: "{back_opacity}",
             }
         }
-        for (i , front_path , back_path) in lon_lines
-            .iter()
-            .enumerate()
-            .map(|(i, points)| {
-                let (front_path, back_path) = transform_to_paths(points);
-                (i, front_path, back_path)
-            })
-        {
-            path {
-                key: "lon-front-{i}",
-                d: front_path,
-                stroke: "#6B8E23",
-                stroke_width: "0.15",
-                stroke_dasharray: "0.5 0.5",
-                fill: "none",
-                opacity: "0.3",
-            }
-            path {
-                key: "lon-back-{i}",
-                d: back_path,
-                stroke: "#6B8E23",
-                stroke_width: "0.15",
-                stroke_dasharray: "0.5 0.5",
-                fill: "none",
-                opacity: "0.1",
+        for (i , (x , y , front , text)) in labels.iter().enumerate() {
+            text {
+                key: "grid-label-{i}",
+                x: "{x}",
+                y: "{y}",
+                fill: "{config.color}",
+                opacity: if *front { (config.opacity * 2.5).min(1.0) } else { back_opacity },
+                font_family: "Arial",
+                font_size: "1.2",
+                text_anchor: "middle",
+                style: "user-select: none;",
+                "{text}"
             }
         }
     }
@@ -4,6 +4,8 @@ use crate::*;
 pub struct GreatCircle {
     pub pole: usize,
     pub name: String,
+    /// Stroke color chosen from the context menu, as `#rrggbb`
+    pub color: Option<String>,
 }
 
 impl GreatCircle {
@@ -11,15 +13,31 @@ impl GreatCircle {
         GreatCircle {
             pole,
             name: String::new(),
+            color: None,
         }
     }
 }
 
+/// Removes the great circle around `pole`, and aligns the grid to the absolute
+/// frame again if it was aligned to that circle
+pub fn remove_great_circle(
+    great_circles: &mut Vec<GreatCircle>,
+    grid: &mut GridConfig,
+    pole: usize,
+) {
+    great_circles.retain(|gc| gc.pole != pole);
+    if grid.reference == Some(pole) {
+        grid.reference = None;
+    }
+}
+
 #[component]
 pub fn GreatCircleDrawer(
     great_circles: Signal<Vec<GreatCircle>>,
     points: Signal<Vec<Point>>,
+    state: Signal<State>,
 ) -> Element {
+    let projection = state.read().projection;
     let calculate_great_circle = |pole: Vec3| -> Vec<Vec3> {
         let mut circle_points = Vec::new();
         let steps = 200;
@@ -40,36 +58,25 @@ pub fn GreatCircleDrawer(
     };
 
     rsx! {
-        for (front_path_data , back_path_data) in great_circles()
+        for (front_path_data , back_path_data , color) in great_circles()
             .iter()
             .map(|gc| {
                 let pole = points()[gc.pole].rotated;
                 let circle_points = calculate_great_circle(pole);
-                let mut front_path = Vec::new();
-                let mut back_path = Vec::new();
-                for [x, y, z] in circle_points {
-                    let svg_x = x * 25.0 + 50.0;
-                    let svg_y = y * 25.0 + 50.0;
-                    if z >= 0.0 {
-                        front_path.push(format!("{},{}", svg_x, svg_y));
-                    } else {
-                        back_path.push(format!("{},{}", svg_x, svg_y));
-                    }
-                }
-                let front_path_data = "M ".to_string() + &front_path.join(" L ");
-                let back_path_data = "M ".to_string() + &back_path.join(" L ");
-                (front_path_data, back_path_data)
+                let (front, back) = projection.view_paths(&circle_points);
+                (front, back, gc.color.clone().unwrap_or_else(|| "lime".to_string()))
             })
         {
             path {
                 d: front_path_data,
-                stroke: "lime",
+                stroke: "{color}",
                 stroke_width: "0.3",
                 fill: "none",
             }
             path {
                 d: back_path_data,
-                stroke: "rgba(0, 255, 0, 0.4)",
+                stroke: "{color}",
+                stroke_opacity: "0.4",
                 stroke_width: "0.3",
                 fill: "none",
             }
@@ -81,7 +88,9 @@ pub fn GreatCircleDrawer(
 pub fn GreatCircleLabels(
     great_circles: Signal<Vec<GreatCircle>>,
     points: Signal<Vec<Point>>,
+    state: Signal<State>,
 ) -> Element {
+    let projection = state.read().projection;
     rsx! {
         for (i , name , x , y) in great_circles()
             .iter()
@@ -93,15 +102,14 @@ pub fn GreatCircleLabels(
                 let pole = points()[gc.pole].rotated;
                 let [px, py, _] = pole;
                 let r2 = (px.powi(2) + py.powi(2)).sqrt();
-                if r2 < 1e-5 {
-                    Some((idx, gc.name.clone(), 75.0, 50.0))
+                // A point of the circle on the limb, or to the right of the center
+                let on_circle = if r2 < 1e-5 {
+                    [1.0, 0.0, 0.0]
                 } else {
-                    let x_intersect = py / r2;
-                    let y_intersect = -px / r2;
-                    let svg_x = x_intersect * 25.0 + 50.0;
-                    let svg_y = y_intersect * 25.0 + 50.0;
-                    Some((idx, gc.name.clone(), svg_x, svg_y))
-                }
+                    [py / r2, -px / r2, 0.0]
+                };
+                let (svg_x, svg_y) = projection.view_position(on_circle)?;
+                Some((idx, gc.name.clone(), svg_x, svg_y))
             })
         {
             text {
@@ -3,9 +3,11 @@ mod coordinate_grid;
 mod great_circle;
 mod small_circle;
 mod snap;
+mod trace;
 
 pub use arc::*;
 pub use coordinate_grid::*;
 pub use great_circle::*;
 pub use small_circle::*;
 pub use snap::*;
+pub use trace::*;
@@ -5,14 +5,27 @@ pub struct SmallCircle {
     pub pole: usize,
     pub plane_distance: f64,
     pub name: String,
+    /// Point lying on the circle, which the plane distance follows when it moves
+    pub through: Option<usize>,
+    /// Stroke color chosen from the context menu, as `#rrggbb`
+    pub color: Option<String>,
 }
 
 impl SmallCircle {
+    pub fn through(pole: usize, plane_distance: f64, point: usize) -> Self {
+        Self {
+            through: Some(point),
+            ..Self::new(pole, plane_distance)
+        }
+    }
+
     pub fn new(pole: usize, plane_distance: f64) -> Self {
         Self {
             pole,
             plane_distance,
             name: String::new(),
+            through: None,
+            color: None,
         }
     }
 }
@@ -21,7 +34,9 @@ impl SmallCircle {
 pub fn SmallCircleDrawer(
     small_circles: Signal<Vec<SmallCircle>>,
     points: Signal<Vec<Point>>,
+    state: Signal<State>,
 ) -> Element {
+    let projection = state.read().projection;
     let calculate_small_circle = |pole: Vec3, distance: f64| -> Vec<Vec3> {
         let mut circle_points = Vec::new();
         let steps = 200;
@@ -53,98 +68,31 @@ pub fn SmallCircleDrawer(
         circle_points
     };
 
-    let transform_to_paths = |points: &Vec<Vec3>| {
-        let mut front_segments = Vec::new();
-        let mut back_segments = Vec::new();
-        let mut current_front = Vec::new();
-        let mut current_back = Vec::new();
-        let mut last_z_positive = None;
-        for i in 0..points.len() {
-            let [x, y, z] = points[i];
-            let z_positive = z >= 0.0;
-            let svg_x = x * 25.0 + 50.0;
-            let svg_y = y * 25.0 + 50.0;
-            if i == 0 {
-                if z_positive {
-                    current_front.push(format!("{},{}", svg_x, svg_y));
-                } else {
-                    current_back.push(format!("{},{}", svg_x, svg_y));
-                }
-                last_z_positive = Some(z_positive);
-                continue;
-            }
-            if let Some(was_positive) = last_z_positive {
-                if was_positive != z_positive {
-                    let prev = points[i - 1];
-                    let t = prev[2] / (prev[2] - z);
-                    let ix = prev[0] + t * (x - prev[0]);
-                    let iy = prev[1] + t * (y - prev[1]);
-                    let isvg_x = ix * 25.0 + 50.0;
-                    let isvg_y = iy * 25.0 + 50.0;
-                    if was_positive {
-                        current_front.push(format!("{},{}", isvg_x, isvg_y));
-                        front_segments.push(current_front);
-                        current_front = Vec::new();
-                        current_back.push(format!("{},{}", isvg_x, isvg_y));
-                    } else {
-                        current_back.push(format!("{},{}", isvg_x, isvg_y));
-                        back_segments.push(current_back);
-                        current_back = Vec::new();
-                        current_front.push(format!("{},{}", isvg_x, isvg_y));
-                    }
-                }
-            }
-            if z_positive {
-                current_front.push(format!("{},{}", svg_x, svg_y));
-            } else {
-                current_back.push(format!("{},{}", svg_x, svg_y));
-            }
-            last_z_positive = Some(z_positive);
-        }
-        if !current_front.is_empty() {
-            front_segments.push(current_front);
-        }
-        if !current_back.is_empty() {
-            back_segments.push(current_back);
-        }
-        let front_data = front_segments
-            .iter()
-            .filter(|segment| !segment.is_empty())
-            .map(|segment| "M ".to_string() + &segment.join(" L "))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let back_data = back_segments
-            .iter()
-            .filter(|segment| !segment.is_empty())
-            .map(|segment| "M ".to_string() + &segment.join(" L "))
-            .collect::<Vec<_>>()
-            .join(" ");
-        (front_data, back_data)
-    };
-
     rsx! {
-        for (i , front_path_data , back_path_data) in small_circles
+        for (i , front_path_data , back_path_data , color) in small_circles
             .read()
             .iter()
             .enumerate()
             .map(|(i, sc)| {
                 let pole = points()[sc.pole].rotated;
                 let circle_points = calculate_small_circle(pole, sc.plane_distance);
-                let (front_path_data, back_path_data) = transform_to_paths(&circle_points);
-                (i, front_path_data, back_path_data)
+                let (front_path_data, back_path_data) = projection.view_paths(&circle_points);
+                let color = sc.color.clone().unwrap_or_else(|| "cyan".to_string());
+                (i, front_path_data, back_path_data, color)
             })
         {
             path {
                 key: "sc-front-{i}",
                 d: front_path_data,
-                stroke: "cyan",
+                stroke: "{color}",
                 stroke_width: "0.3",
                 fill: "none",
             }
             path {
                 key: "sc-back-{i}",
                 d: back_path_data,
-                stroke: "rgba(0, 255, 255, 0.4)",
+                stroke: "{color}",
+                stroke_opacity: "0.4",
                 stroke_width: "0.3",
                 fill: "none",
             }
@@ -156,17 +104,18 @@ pub fn SmallCircleDrawer(
 pub fn SmallCircleLabels(
     small_circles: Signal<Vec<SmallCircle>>,
     points: Signal<Vec<Point>>,
+    state: Signal<State>,
 ) -> Element {
+    let projection = state.read().projection;
     rsx! {
         for (i , svg_x , svg_y , opacity , name) in small_circles
             .read()
             .iter()
             .enumerate()
             .filter_map(|(i, sc)| {
-                let [x, y, z] = points()[sc.pole].rotated;
-                let svg_x = x * 25.0 + 50.0;
-                let svg_y = y * 25.0 + 50.0;
-                let opacity = if z > 0.0 { 1.0 } else { 0.4 };
+                let pole = points()[sc.pole].rotated;
+                let (svg_x, svg_y) = projection.view_position(pole)?;
+                let opacity = if projection.is_front(pole) { 1.0 } else { 0.4 };
                 if !sc.name.is_empty() {
                     Some((i, svg_x, svg_y, opacity, &sc.name))
                 } else {
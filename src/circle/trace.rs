@@ -0,0 +1,101 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Smallest movement (chord length) that adds a new vertex to a trace
+const TRACE_MIN_STEP: f64 = 0.003;
+
+/// Path left behind by a point as the points it depends on move
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+    /// Traced point, `None` once it has been deleted
+    pub point: Option<usize>,
+    /// Recorded absolute positions, oldest first
+    pub path: Vec<Vec3>,
+    pub recording: bool,
+}
+
+impl Trace {
+    pub fn new(point: &Point) -> Self {
+        Trace {
+            point: Some(point.id),
+            path: vec![point.absolute],
+            recording: true,
+        }
+    }
+}
+
+/// Appends the current position of every traced point that moved far enough
+pub fn record_traces(traces: &mut [Trace], points: &[Point]) {
+    for trace in traces.iter_mut().filter(|t| t.recording) {
+        let Some(point) = trace.point.and_then(|id| points.get(id)) else {
+            continue;
+        };
+        let moved = match trace.path.last() {
+            Some(last) => {
+                let [dx, dy, dz] = [
+                    point.absolute[0] - last[0],
+                    point.absolute[1] - last[1],
+                    point.absolute[2] - last[2],
+                ];
+                (dx * dx + dy * dy + dz * dz).sqrt() >= TRACE_MIN_STEP
+            }
+            None => true,
+        };
+        if moved {
+            trace.path.push(point.absolute);
+        }
+    }
+}
+
+/// Fixes trace references after point `removed` was deleted with `swap_remove`,
+/// which moved the point at `moved_from` into its slot. Traces of the removed
+/// point stay on the sphere but stop recording.
+pub fn fix_trace_indices(traces: &mut [Trace], removed: usize, moved_from: usize) {
+    for trace in traces.iter_mut() {
+        if trace.point == Some(removed) {
+            trace.point = None;
+            trace.recording = false;
+        } else if trace.point == Some(moved_from) {
+            trace.point = Some(removed);
+        }
+    }
+}
+
+#[component]
+pub fn TraceDrawer(state: Signal<State>) -> Element {
+    let q = state.read().quaternion;
+    let projection = state.read().projection;
+
+    rsx! {
+        for (i , front_path_data , back_path_data) in state
+            .read()
+            .traces
+            .iter()
+            .enumerate()
+            .map(|(i, trace)| {
+                let rotated = trace
+                    .path
+                    .iter()
+                    .map(|&p| q.rotate_point_active(p))
+                    .collect::<Vec<_>>();
+                let (front, back) = projection.view_paths(&rotated);
+                (i, front, back)
+            })
+        {
+            path {
+                key: "trace-front-{i}",
+                d: front_path_data,
+                stroke: "#FF4DD2",
+                stroke_width: "0.25",
+                fill: "none",
+            }
+            path {
+                key: "trace-back-{i}",
+                d: back_path_data,
+                stroke: "rgba(255, 77, 210, 0.4)",
+                stroke_width: "0.25",
+                fill: "none",
+            }
+        }
+    }
+}
@@ -0,0 +1,1056 @@
+use crate::*;
+
+/// Number-valued expression, such as `90 - phi`. Angles are in degrees, with or
+/// without a `°` sign.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    /// A parameter, or, where an object is expected, the name of a point or circle;
+    /// `#3` is the point with id 3
+    Name(String),
+    Negate(Box<Expression>),
+    /// `+`, `-`, `*` or `/` applied to two expressions
+    Binary(char, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Value of an expression that uses no names
+    pub fn constant(&self) -> Option<f64> {
+        match self {
+            Expression::Number(number) => Some(*number),
+            Expression::Name(_) => None,
+            Expression::Negate(expression) => expression.constant().map(|value| -value),
+            Expression::Binary(operator, a, b) => {
+                let (a, b) = (a.constant()?, b.constant()?);
+                Some(match operator {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandArgument {
+    /// Keyword of the argument, as `lat` in `lat=30`
+    pub key: Option<String>,
+    pub value: Expression,
+}
+
+/// One statement of the command language
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// `phi = 52` sets a parameter, which later commands can use in numbers
+    Parameter { name: String, value: Expression },
+    /// `C = intersect(g1, g2)` runs a function; the name before `=` is given
+    /// to the point or circle it creates
+    Call {
+        target: Option<String>,
+        function: String,
+        arguments: Vec<CommandArgument>,
+    },
+}
+
+/// Usage of each function of the command language
+pub const COMMAND_HELP: [&str; 10] = [
+    "A = point(lat=30, lon=45) — point at grid coordinates",
+    "g = gc(A) or gc(A, B) — great circle with pole A, or through A and B",
+    "s = sc(P, radius=20°), sc(P, B) or sc(A, B, C) — small circle",
+    "arc(A, B) — arc between two points",
+    "C = intersect(g1, g2) — intersection of two great circles",
+    "B = antipode(A) — point opposite to A",
+    "P = pole(A, B) or pole(A, B, C) — pole of the circle through the points",
+    "hide(A) — hide a point",
+    "phi = 52 — parameter for later numbers, as in point(lat=90 - phi, lon=0)",
+    "help() — list the commands",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Number(f64),
+    Symbol(char),
+}
+
+/// Splits a statement into tokens, each with its column (counting from 1)
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_alphabetic() || c == '_' || c == '#' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '\''))
+            {
+                i += 1;
+            }
+            tokens.push((Token::Name(chars[start..i].iter().collect()), column));
+        } else if c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&c| c == '"')
+                .ok_or(format!("Column {column}: the quoted name is not closed"))?;
+            tokens.push((
+                Token::Name(chars[i + 1..i + 1 + end].iter().collect()),
+                column,
+            ));
+            i += end + 2;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| format!("Column {column}: `{text}` is not a number"))?;
+            // Angles may carry a degree sign
+            if i < chars.len() && chars[i] == '°' {
+                i += 1;
+            }
+            tokens.push((Token::Number(number), column));
+        } else if matches!(c, '(' | ')' | ',' | '=' | '+' | '-' | '*' | '/') {
+            tokens.push((Token::Symbol(c), column));
+            i += 1;
+        } else {
+            return Err(format!("Column {column}: unexpected `{c}`"));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// Column just after the statement
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |&(_, column)| column)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek(0).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        let found = self.peek(0) == Some(&Token::Symbol(symbol));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(format!("Column {}: expected `{symbol}`", self.column()))
+        }
+    }
+
+    /// Sums and differences of terms
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut expression = self.term()?;
+        while let Some(&Token::Symbol(operator @ ('+' | '-'))) = self.peek(0) {
+            self.position += 1;
+            expression = Expression::Binary(operator, Box::new(expression), Box::new(self.term()?));
+        }
+        Ok(expression)
+    }
+
+    /// Products and quotients of factors
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut expression = self.factor()?;
+        while let Some(&Token::Symbol(operator @ ('*' | '/'))) = self.peek(0) {
+            self.position += 1;
+            expression =
+                Expression::Binary(operator, Box::new(expression), Box::new(self.factor()?));
+        }
+        Ok(expression)
+    }
+
+    fn factor(&mut self) -> Result<Expression, String> {
+        let column = self.column();
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expression::Number(number)),
+            Some(Token::Name(name)) => Ok(Expression::Name(name)),
+            Some(Token::Symbol('-')) => Ok(Expression::Negate(Box::new(self.factor()?))),
+            Some(Token::Symbol('+')) => self.factor(),
+            Some(Token::Symbol('(')) => {
+                let expression = self.expression()?;
+                self.expect(')')?;
+                Ok(expression)
+            }
+            _ => Err(format!("Column {column}: expected a value")),
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<CommandArgument>, String> {
+        let mut arguments = Vec::new();
+        if self.eat(')') {
+            return Ok(arguments);
+        }
+        loop {
+            let key = match (self.peek(0), self.peek(1)) {
+                (Some(Token::Name(key)), Some(Token::Symbol('='))) => {
+                    let key = key.clone();
+                    self.position += 2;
+                    Some(key)
+                }
+                _ => None,
+            };
+            let value = self.expression()?;
+            arguments.push(CommandArgument { key, value });
+            if self.eat(')') {
+                return Ok(arguments);
+            }
+            if !self.eat(',') {
+                return Err(format!("Column {}: expected `,` or `)`", self.column()));
+            }
+        }
+    }
+}
+
+/// Parses a statement: `[name =] function(argument, key=argument, ...)`, or
+/// `name = number` for a parameter
+pub fn parse_command(text: &str) -> Result<Command, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+        end: text.chars().count() + 1,
+    };
+    let Some(Token::Name(first)) = parser.next() else {
+        return Err("A command starts with a name, as in `gc(A)`".to_string());
+    };
+
+    let command = if parser.eat('=') {
+        match (parser.peek(0), parser.peek(1)) {
+            (Some(Token::Name(function)), Some(Token::Symbol('('))) => {
+                let function = function.to_lowercase();
+                parser.position += 2;
+                Command::Call {
+                    target: Some(first),
+                    function,
+                    arguments: parser.arguments()?,
+                }
+            }
+            _ => Command::Parameter {
+                name: first,
+                value: parser.expression()?,
+            },
+        }
+    } else {
+        parser.expect('(')?;
+        Command::Call {
+            target: None,
+            function: first.to_lowercase(),
+            arguments: parser.arguments()?,
+        }
+    };
+
+    if parser.position < parser.tokens.len() {
+        return Err(format!(
+            "Column {}: unexpected text after the command",
+            parser.column()
+        ));
+    }
+    Ok(command)
+}
+
+/// The parts of a diagram that commands change
+pub struct Diagram<'a> {
+    pub points: &'a mut Vec<Point>,
+    pub arcs: &'a mut Vec<(usize, usize)>,
+    pub great_circles: &'a mut Vec<GreatCircle>,
+    pub small_circles: &'a mut Vec<SmallCircle>,
+    /// Values set by parameter statements
+    pub parameters: &'a mut Vec<(String, f64)>,
+    pub grid: &'a GridConfig,
+    pub quaternion: Quaternion,
+}
+
+/// What a name in a command refers to
+enum Object {
+    Point(usize),
+    GreatCircle(usize),
+    SmallCircle,
+}
+
+impl Diagram<'_> {
+    fn lookup(&self, name: &str) -> Result<Object, String> {
+        if let Some(id) = name.strip_prefix('#') {
+            return id
+                .parse::<usize>()
+                .ok()
+                .filter(|&id| id < self.points.len())
+                .map(Object::Point)
+                .ok_or(format!("There is no point {name}"));
+        }
+        if let Some(point) = self.points.iter().find(|point| point.name == name) {
+            return Ok(Object::Point(point.id));
+        }
+        if let Some(i) = self.great_circles.iter().position(|gc| gc.name == name) {
+            return Ok(Object::GreatCircle(i));
+        }
+        if self.small_circles.iter().any(|sc| sc.name == name) {
+            return Ok(Object::SmallCircle);
+        }
+        Err(format!("Nothing is named {name}"))
+    }
+
+    fn parameter(&self, name: &str) -> Option<f64> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|&(_, value)| value)
+    }
+
+    fn number(&self, expression: &Expression) -> Result<f64, String> {
+        let value = match expression {
+            Expression::Number(number) => *number,
+            Expression::Name(name) => match self.parameter(name) {
+                Some(value) => value,
+                None if self.lookup(name).is_ok() => {
+                    return Err(format!("{name} is an object, not a number"))
+                }
+                None => return Err(format!("Unknown parameter {name}")),
+            },
+            Expression::Negate(expression) => -self.number(expression)?,
+            Expression::Binary(operator, a, b) => {
+                let (a, b) = (self.number(a)?, self.number(b)?);
+                match operator {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                }
+            }
+        };
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err("The value is not a finite number".to_string())
+        }
+    }
+
+    fn point(&self, expression: &Expression) -> Result<usize, String> {
+        let Expression::Name(name) = expression else {
+            return Err("Expected a point, not a number".to_string());
+        };
+        match self.lookup(name)? {
+            Object::Point(id) => Ok(id),
+            _ => Err(format!("{name} is a circle, not a point")),
+        }
+    }
+
+    /// Pole of a great circle, given by its name or by its pole
+    fn great_circle_pole(&self, expression: &Expression) -> Result<usize, String> {
+        let Expression::Name(name) = expression else {
+            return Err("Expected a great circle, not a number".to_string());
+        };
+        match self.lookup(name)? {
+            Object::GreatCircle(i) => Ok(self.great_circles[i].pole),
+            Object::Point(id) => Ok(id),
+            Object::SmallCircle => Err(format!("{name} is a small circle")),
+        }
+    }
+
+    fn push_point(&mut self, position: Vec3, construction: Option<Construction>) -> usize {
+        let id = self.points.len();
+        let mut point = Point::from_vec3_absolute(id, position, self.quaternion);
+        point.construction = construction;
+        self.points.push(point);
+        id
+    }
+
+    fn derived_point(&mut self, construction: Construction) -> Result<usize, String> {
+        let position = construction
+            .compute(self.points)
+            .ok_or("The construction is degenerate")?;
+        Ok(self.push_point(position, Some(construction)))
+    }
+
+    /// Pole of the circle through two or three points
+    fn pole(&mut self, points: &[&Expression]) -> Result<usize, String> {
+        match *points {
+            [a, b] => {
+                let (a, b) = (self.point(a)?, self.point(b)?);
+                if a == b {
+                    return Err("The points must differ".to_string());
+                }
+                self.derived_point(Construction::GreatCirclePole(a, b))
+            }
+            [a, b, c] => {
+                let [a, b, c] = [self.point(a)?, self.point(b)?, self.point(c)?];
+                self.derived_point(Construction::SmallCirclePole(a, b, c))
+                    .map_err(|_| "The three points lie on one great circle".to_string())
+            }
+            _ => Err("Give two points for a great circle, or three for a small circle".to_string()),
+        }
+    }
+
+    fn describe_point(&self, id: usize) -> String {
+        match self.points[id].name.as_str() {
+            "" => format!("point #{id}"),
+            name => format!("point {name}"),
+        }
+    }
+}
+
+/// Arguments of a call, matched to the parameter names of its function
+struct Arguments<'a>(&'a [CommandArgument]);
+
+impl<'a> Arguments<'a> {
+    /// The argument called `key`, or else the positional argument at `position`
+    fn get(&self, key: &str, position: usize) -> Option<&'a Expression> {
+        self.0
+            .iter()
+            .find(|argument| argument.key.as_deref() == Some(key))
+            .or_else(|| {
+                self.0
+                    .iter()
+                    .filter(|argument| argument.key.is_none())
+                    .nth(position)
+            })
+            .map(|argument| &argument.value)
+    }
+
+    fn number(&self, diagram: &Diagram, key: &str, position: usize) -> Result<f64, String> {
+        let value = self.get(key, position).ok_or(format!("{key} is missing"))?;
+        diagram
+            .number(value)
+            .map_err(|error| format!("{key}: {error}"))
+    }
+
+    fn positional(&self) -> Vec<&'a Expression> {
+        self.0
+            .iter()
+            .filter(|argument| argument.key.is_none())
+            .map(|argument| &argument.value)
+            .collect()
+    }
+
+    /// Rejects keywords the function does not know
+    fn check_keys(&self, keys: &[&str]) -> Result<(), String> {
+        match self
+            .0
+            .iter()
+            .filter_map(|argument| argument.key.as_deref())
+            .find(|key| !keys.contains(key))
+        {
+            Some(key) => Err(format!("Unknown argument {key}")),
+            None => Ok(()),
+        }
+    }
+
+    /// The `N` positional arguments of a function that takes exactly that many
+    fn exactly<const N: usize>(&self, function: &str) -> Result<[&'a Expression; N], String> {
+        let positional = self.positional();
+        let count = positional.len();
+        positional
+            .try_into()
+            .map_err(|_| format!("{function} takes {N} arguments, not {count}"))
+    }
+}
+
+/// Runs a command against the diagram and describes what it did
+pub fn run_command(command: &Command, diagram: &mut Diagram) -> Result<String, String> {
+    let (target, function, arguments) = match command {
+        Command::Parameter { name, value } => {
+            if diagram.lookup(name).is_ok() {
+                return Err(format!("{name} is already an object"));
+            }
+            let value = diagram.number(value)?;
+            diagram
+                .parameters
+                .retain(|(parameter, _)| parameter != name);
+            diagram.parameters.push((name.clone(), value));
+            return Ok(format!("{name} = {value}"));
+        }
+        Command::Call {
+            target,
+            function,
+            arguments,
+        } => (target, function, Arguments(arguments)),
+    };
+    if let Some(target) = target {
+        if target.starts_with('#') {
+            return Err(format!("{target} cannot be used as a name"));
+        }
+        if diagram.lookup(target).is_ok() || diagram.parameter(target).is_some() {
+            return Err(format!("{target} is already defined"));
+        }
+    }
+    let name = target.clone().unwrap_or_default();
+
+    match function.as_str() {
+        "point" => {
+            arguments.check_keys(&["lat", "lon"])?;
+            let lat = arguments.number(diagram, "lat", 0)?;
+            let lon = arguments.number(diagram, "lon", 1)?;
+            if lat.abs() > 90.0 {
+                return Err("lat must be between -90° and 90°".to_string());
+            }
+            let position = diagram.grid.frame_position(diagram.points, [lat, lon]);
+            let id = diagram.push_point(position, None);
+            diagram.points[id].name = name;
+            Ok(format!("Added {}", diagram.describe_point(id)))
+        }
+        "antipode" => {
+            arguments.check_keys(&[])?;
+            let [point] = arguments.exactly("antipode")?;
+            let id = diagram.derived_point(Construction::Antipode(diagram.point(point)?))?;
+            diagram.points[id].name = name;
+            Ok(format!("Added {}", diagram.describe_point(id)))
+        }
+        "pole" => {
+            arguments.check_keys(&[])?;
+            let id = diagram.pole(&arguments.positional())?;
+            diagram.points[id].name = name;
+            Ok(format!("Added {}", diagram.describe_point(id)))
+        }
+        "intersect" => {
+            arguments.check_keys(&[])?;
+            let [a, b] = arguments.exactly("intersect")?;
+            let (a, b) = (diagram.great_circle_pole(a)?, diagram.great_circle_pole(b)?);
+            if a == b {
+                return Err("A great circle does not intersect itself".to_string());
+            }
+            let id = diagram.derived_point(Construction::Intersection(a, b))?;
+            diagram.points[id].name = name;
+            Ok(format!(
+                "Added {}; its antipode is the other intersection",
+                diagram.describe_point(id)
+            ))
+        }
+        "gc" => {
+            arguments.check_keys(&[])?;
+            let pole = match arguments.positional().as_slice() {
+                [pole] => diagram.point(pole)?,
+                [a, b] => diagram.pole(&[a, b])?,
+                _ => return Err("gc takes a pole, or two points on the circle".to_string()),
+            };
+            if diagram.great_circles.iter().any(|gc| gc.pole == pole) {
+                return Err(format!(
+                    "{} is already the pole of a great circle",
+                    diagram.describe_point(pole)
+                ));
+            }
+            let mut great_circle = GreatCircle::new(pole);
+            great_circle.name = name;
+            diagram.great_circles.push(great_circle);
+            Ok(format!(
+                "Added a great circle with pole {}",
+                diagram.describe_point(pole)
+            ))
+        }
+        "sc" => {
+            arguments.check_keys(&["radius"])?;
+            let positional = arguments.positional();
+            let mut small_circle = match positional.as_slice() {
+                [pole] => {
+                    let radius = arguments.number(diagram, "radius", 1)?;
+                    if !(0.0 < radius && radius < 180.0) {
+                        return Err("radius must be between 0° and 180°".to_string());
+                    }
+                    SmallCircle::new(diagram.point(pole)?, radius.to_radians().cos())
+                }
+                [pole, through] => {
+                    let (pole, through) = (diagram.point(pole)?, diagram.point(through)?);
+                    if pole == through {
+                        return Err("A small circle cannot pass through its own pole".to_string());
+                    }
+                    let [a, b] = [pole, through].map(|id| diagram.points[id].absolute);
+                    let plane_distance = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+                    SmallCircle::through(pole, plane_distance.clamp(-1.0, 1.0), through)
+                }
+                [a, _, _] => {
+                    let through = diagram.point(a)?;
+                    let pole = diagram.pole(&positional)?;
+                    let [p, q] = [pole, through].map(|id| diagram.points[id].absolute);
+                    let plane_distance = p[0] * q[0] + p[1] * q[1] + p[2] * q[2];
+                    SmallCircle::through(pole, plane_distance.clamp(-1.0, 1.0), through)
+                }
+                _ => {
+                    return Err(
+                        "sc takes a pole and a radius, a pole and a point, or three points"
+                            .to_string(),
+                    )
+                }
+            };
+            let pole = small_circle.pole;
+            if diagram.small_circles.iter().any(|sc| sc.pole == pole) {
+                return Err(format!(
+                    "{} is already the pole of a small circle",
+                    diagram.describe_point(pole)
+                ));
+            }
+            small_circle.name = name;
+            diagram.small_circles.push(small_circle);
+            Ok(format!(
+                "Added a small circle with pole {}",
+                diagram.describe_point(pole)
+            ))
+        }
+        "arc" => {
+            arguments.check_keys(&[])?;
+            if target.is_some() {
+                return Err("Arcs cannot be named".to_string());
+            }
+            let [a, b] = arguments.exactly("arc")?;
+            let (a, b) = (diagram.point(a)?, diagram.point(b)?);
+            if a == b {
+                return Err("An arc needs two different points".to_string());
+            }
+            if diagram.arcs.contains(&(a, b)) || diagram.arcs.contains(&(b, a)) {
+                return Err("The arc exists already".to_string());
+            }
+            diagram.arcs.push((a, b));
+            Ok(format!(
+                "Added an arc from {} to {}",
+                diagram.describe_point(a),
+                diagram.describe_point(b)
+            ))
+        }
+        "hide" => {
+            arguments.check_keys(&[])?;
+            let [point] = arguments.exactly("hide")?;
+            let id = diagram.point(point)?;
+            diagram.points[id].hidden = true;
+            Ok(format!("Hid {}", diagram.describe_point(id)))
+        }
+        "help" => Ok(COMMAND_HELP.join("\n")),
+        function => Err(format!(
+            "Unknown command {function}; type help() for the list"
+        )),
+    }
+}
+
+/// Parses and runs a command typed in the console
+pub fn execute_command(
+    text: &str,
+    parameters: &mut Vec<(String, f64)>,
+    mut points: Signal<Vec<Point>>,
+    mut arcs: Signal<Vec<(usize, usize)>>,
+    mut great_circles: Signal<Vec<GreatCircle>>,
+    mut small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+) -> Result<String, String> {
+    let command = parse_command(text)?;
+    let state = state.read();
+    run_command(
+        &command,
+        &mut Diagram {
+            points: &mut points.write(),
+            arcs: &mut arcs.write(),
+            great_circles: &mut great_circles.write(),
+            small_circles: &mut small_circles.write(),
+            parameters,
+            grid: &state.grid,
+            quaternion: state.quaternion,
+        },
+    )
+}
+
+/// Most results kept in the console's log
+const CONSOLE_LOG_LENGTH: usize = 6;
+
+/// Input line for typed commands, with the latest results above it. Up and Down
+/// recall earlier commands.
+#[component]
+pub fn CommandConsole(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+) -> Element {
+    let mut input = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut log = use_signal(Vec::<(String, String)>::new);
+    let mut history = use_signal(Vec::<String>::new);
+    let mut parameters = use_signal(Vec::<(String, f64)>::new);
+    // Position in the history while recalling, `None` when editing a new command
+    let mut recalled = use_signal(|| None::<usize>);
+
+    let mut submit = move || {
+        let text = input().trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        if history.read().last() != Some(&text) {
+            history.write().push(text.clone());
+        }
+        recalled.set(None);
+        let result = execute_command(
+            &text,
+            &mut parameters.write(),
+            points,
+            arcs,
+            great_circles,
+            small_circles,
+            state,
+        );
+        match result {
+            Ok(message) => {
+                let mut log = log.write();
+                log.push((text, message));
+                let excess = log.len().saturating_sub(CONSOLE_LOG_LENGTH);
+                log.drain(..excess);
+                input.set(String::new());
+                error.set(None);
+            }
+            // The command stays in the input to be corrected
+            Err(message) => error.set(Some(message)),
+        }
+    };
+
+    rsx! {
+        div { class: "command-console",
+            for (i , (command , message)) in log().into_iter().enumerate() {
+                div { key: "{i}", class: "command-log",
+                    div { class: "command-echo", "> {command}" }
+                    div { class: "command-result", "{message}" }
+                }
+            }
+            if let Some(message) = error() {
+                div { class: "command-error", "{message}" }
+            }
+            input {
+                class: "command-input",
+                r#type: "text",
+                placeholder: "Command, e.g. A = point(lat=30, lon=45) — help() lists them",
+                spellcheck: "false",
+                value: "{input}",
+                oninput: move |evt| {
+                    input.set(evt.value());
+                    error.set(None);
+                },
+                onkeydown: move |evt| {
+                    let len = history.read().len();
+                    match evt.key() {
+                        Key::Enter => submit(),
+                        Key::ArrowUp if len > 0 => {
+                            let i = recalled().map_or(len - 1, |i| i.saturating_sub(1));
+                            recalled.set(Some(i));
+                            input.set(history.read()[i].clone());
+                            evt.prevent_default();
+                        }
+                        Key::ArrowDown => {
+                            if let Some(i) = recalled() {
+                                if i + 1 < len {
+                                    recalled.set(Some(i + 1));
+                                    input.set(history.read()[i + 1].clone());
+                                } else {
+                                    recalled.set(None);
+                                    input.set(String::new());
+                                }
+                                evt.prevent_default();
+                            }
+                        }
+                        Key::Escape => {
+                            input.set(String::new());
+                            error.set(None);
+                            recalled.set(None);
+                        }
+                        _ => {}
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Diagram owned by a test, which commands are run against
+    #[derive(Default)]
+    struct Fixture {
+        points: Vec<Point>,
+        arcs: Vec<(usize, usize)>,
+        great_circles: Vec<GreatCircle>,
+        small_circles: Vec<SmallCircle>,
+        parameters: Vec<(String, f64)>,
+        grid: GridConfig,
+    }
+
+    impl Fixture {
+        fn run(&mut self, text: &str) -> Result<String, String> {
+            let command = parse_command(text)?;
+            run_command(
+                &command,
+                &mut Diagram {
+                    points: &mut self.points,
+                    arcs: &mut self.arcs,
+                    great_circles: &mut self.great_circles,
+                    small_circles: &mut self.small_circles,
+                    parameters: &mut self.parameters,
+                    grid: &self.grid,
+                    quaternion: Quaternion::identity(),
+                },
+            )
+        }
+
+        /// Fixture with the points `A` at (0°, 0°), `B` at (0°, 90°) and `C` at (45°, 45°)
+        fn with_points() -> Self {
+            let mut fixture = Fixture::default();
+            for command in ["A = point(0, 0)", "B = point(0, 90)", "C = point(45, 45)"] {
+                fixture.run(command).unwrap();
+            }
+            fixture
+        }
+    }
+
+    fn number(value: f64) -> Box<Expression> {
+        Box::new(Expression::Number(value))
+    }
+
+    #[test]
+    fn tokenizer_rejects_bad_input() {
+        assert_eq!(
+            tokenize("A = point(1, 2) ; x").unwrap_err(),
+            "Column 17: unexpected `;`"
+        );
+        assert_eq!(
+            tokenize("A = \"open").unwrap_err(),
+            "Column 5: the quoted name is not closed"
+        );
+        assert_eq!(
+            tokenize("point(1.2.3)").unwrap_err(),
+            "Column 7: `1.2.3` is not a number"
+        );
+    }
+
+    #[test]
+    fn tokenizer_reads_degrees_and_quoted_names() {
+        let tokens: Vec<Token> = tokenize("\"North pole\" = point(90°, 0)")
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(tokens[0], Token::Name("North pole".to_string()));
+        assert!(tokens.contains(&Token::Number(90.0)));
+        assert!(!tokens.contains(&Token::Name("°".to_string())));
+    }
+
+    #[test]
+    fn parser_respects_precedence() {
+        let Command::Parameter { value, .. } = parse_command("x = 1 + 2 * -3").unwrap() else {
+            panic!("expected a parameter");
+        };
+        assert_eq!(
+            value,
+            Expression::Binary(
+                '+',
+                number(1.0),
+                Box::new(Expression::Binary(
+                    '*',
+                    number(2.0),
+                    Box::new(Expression::Negate(number(3.0)))
+                ))
+            )
+        );
+        assert_eq!(value.constant(), Some(-5.0));
+    }
+
+    #[test]
+    fn parser_reads_keyword_arguments() {
+        let command = parse_command("A = Point(lat=30, lon=phi)").unwrap();
+        let Command::Call {
+            target,
+            function,
+            arguments,
+        } = command
+        else {
+            panic!("expected a call");
+        };
+        assert_eq!(target.as_deref(), Some("A"));
+        assert_eq!(function, "point");
+        assert_eq!(arguments[0].key.as_deref(), Some("lat"));
+        assert_eq!(arguments[1].value, Expression::Name("phi".to_string()));
+    }
+
+    #[test]
+    fn parser_reports_errors_with_columns() {
+        assert_eq!(
+            parse_command("42").unwrap_err(),
+            "A command starts with a name, as in `gc(A)`"
+        );
+        assert_eq!(parse_command("gc A").unwrap_err(), "Column 4: expected `(`");
+        assert_eq!(
+            parse_command("gc(A B)").unwrap_err(),
+            "Column 6: expected `,` or `)`"
+        );
+        assert_eq!(
+            parse_command("x = (1 + 2").unwrap_err(),
+            "Column 11: expected `)`"
+        );
+        assert_eq!(
+            parse_command("gc(A) B").unwrap_err(),
+            "Column 7: unexpected text after the command"
+        );
+        assert_eq!(
+            parse_command("x = 1 +").unwrap_err(),
+            "Column 8: expected a value"
+        );
+    }
+
+    #[test]
+    fn parameters_feed_later_numbers() {
+        let mut fixture = Fixture::default();
+        fixture.run("phi = 30").unwrap();
+        fixture.run("A = point(lat=90 - phi, lon=0)").unwrap();
+        let [lat, _] = fixture
+            .grid
+            .frame_coordinates(&fixture.points, fixture.points[0].absolute);
+        assert!((lat - 60.0).abs() < 1e-9);
+        assert_eq!(
+            fixture.run("B = point(lat=psi, lon=0)").unwrap_err(),
+            "lat: Unknown parameter psi"
+        );
+        assert_eq!(
+            fixture.run("B = point(lat=A, lon=0)").unwrap_err(),
+            "lat: A is an object, not a number"
+        );
+        assert_eq!(
+            fixture.run("B = point(lat=1 / 0, lon=0)").unwrap_err(),
+            "lat: The value is not a finite number"
+        );
+    }
+
+    #[test]
+    fn names_must_be_new() {
+        let mut fixture = Fixture::with_points();
+        assert_eq!(
+            fixture.run("A = point(10, 10)").unwrap_err(),
+            "A is already defined"
+        );
+        assert_eq!(fixture.run("A = 5").unwrap_err(), "A is already an object");
+        assert_eq!(
+            fixture.run("#1 = point(10, 10)").unwrap_err(),
+            "#1 cannot be used as a name"
+        );
+        assert_eq!(
+            fixture.run("point(100, 0)").unwrap_err(),
+            "lat must be between -90° and 90°"
+        );
+        assert_eq!(
+            fixture.run("frobnicate(A)").unwrap_err(),
+            "Unknown command frobnicate; type help() for the list"
+        );
+        assert_eq!(
+            fixture.run("point(lat=1, lon=2, height=3)").unwrap_err(),
+            "Unknown argument height"
+        );
+    }
+
+    #[test]
+    fn great_circles_are_unique_per_pole() {
+        let mut fixture = Fixture::with_points();
+        fixture.run("g = gc(A)").unwrap();
+        assert_eq!(
+            fixture.run("gc(A)").unwrap_err(),
+            "point A is already the pole of a great circle"
+        );
+        // Through two points, a new pole point is derived each time
+        fixture.run("gc(B, C)").unwrap();
+        assert_eq!(fixture.great_circles.len(), 2);
+        assert_eq!(
+            fixture.run("gc()").unwrap_err(),
+            "gc takes a pole, or two points on the circle"
+        );
+    }
+
+    #[test]
+    fn small_circles_are_unique_per_pole() {
+        let mut fixture = Fixture::with_points();
+        fixture.run("s = sc(A, radius=20)").unwrap();
+        assert_eq!(
+            fixture.run("sc(A, radius=30)").unwrap_err(),
+            "point A is already the pole of a small circle"
+        );
+        assert_eq!(
+            fixture.run("sc(A, B)").unwrap_err(),
+            "point A is already the pole of a small circle"
+        );
+        assert_eq!(fixture.small_circles.len(), 1);
+        assert!((fixture.small_circles[0].plane_distance - 20f64.to_radians().cos()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn small_circles_need_a_radius() {
+        let mut fixture = Fixture::with_points();
+        assert_eq!(
+            fixture.run("sc(B, B)").unwrap_err(),
+            "A small circle cannot pass through its own pole"
+        );
+        assert_eq!(
+            fixture.run("sc(B, radius=0)").unwrap_err(),
+            "radius must be between 0° and 180°"
+        );
+        assert_eq!(
+            fixture.run("sc(B, radius=180)").unwrap_err(),
+            "radius must be between 0° and 180°"
+        );
+        fixture.run("sc(B, C)").unwrap();
+        assert_eq!(fixture.small_circles[0].through, Some(2));
+    }
+
+    #[test]
+    fn constructions_reject_degenerate_input() {
+        let mut fixture = Fixture::with_points();
+        fixture.run("g = gc(A)").unwrap();
+        assert_eq!(
+            fixture.run("intersect(g, g)").unwrap_err(),
+            "A great circle does not intersect itself"
+        );
+        assert_eq!(
+            fixture.run("pole(A, A)").unwrap_err(),
+            "The points must differ"
+        );
+        assert_eq!(
+            fixture.run("arc(A, A)").unwrap_err(),
+            "An arc needs two different points"
+        );
+        fixture.run("arc(A, B)").unwrap();
+        assert_eq!(
+            fixture.run("arc(B, A)").unwrap_err(),
+            "The arc exists already"
+        );
+        assert_eq!(
+            fixture.run("a = arc(B, C)").unwrap_err(),
+            "Arcs cannot be named"
+        );
+        assert_eq!(
+            fixture.run("antipode(A, B)").unwrap_err(),
+            "antipode takes 1 arguments, not 2"
+        );
+        assert_eq!(
+            fixture.run("antipode(g)").unwrap_err(),
+            "g is a circle, not a point"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+const SOLVER_ITERATIONS: usize = 40;
+const SOLVER_TOLERANCE: f64 = 1e-7;
+
+/// Geometric relation between free points, kept satisfied while dragging
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Constraint {
+    /// Fixed angular distance (radians) between two points
+    Distance { a: usize, b: usize, angle: f64 },
+    /// Point stays on the great or small circle with the given pole
+    OnCircle { point: usize, pole: usize },
+    /// Arc `a`-`b` is as long as arc `c`-`d`
+    EqualSides {
+        a: usize,
+        b: usize,
+        c: usize,
+        d: usize,
+    },
+    /// The arcs from `vertex` to `a` and to `b` meet at a right angle
+    RightAngle { vertex: usize, a: usize, b: usize },
+}
+
+impl Constraint {
+    pub fn points(&self) -> Vec<usize> {
+        match *self {
+            Constraint::Distance { a, b, .. } => vec![a, b],
+            Constraint::OnCircle { point, pole } => vec![point, pole],
+            Constraint::EqualSides { a, b, c, d } => vec![a, b, c, d],
+            Constraint::RightAngle { vertex, a, b } => vec![vertex, a, b],
+        }
+    }
+
+    /// Replaces references to point `from` with `to`
+    pub fn remap(&mut self, from: usize, to: usize) {
+        let fix = |id: &mut usize| {
+            if *id == from {
+                *id = to;
+            }
+        };
+        match self {
+            Constraint::Distance { a, b, .. } => {
+                fix(a);
+                fix(b);
+            }
+            Constraint::OnCircle { point, pole } => {
+                fix(point);
+                fix(pole);
+            }
+            Constraint::EqualSides { a, b, c, d } => {
+                fix(a);
+                fix(b);
+                fix(c);
+                fix(d);
+            }
+            Constraint::RightAngle { vertex, a, b } => {
+                fix(vertex);
+                fix(a);
+                fix(b);
+            }
+        }
+    }
+
+    /// Short description naming the points, e.g. `|A–B| = 30.00°`
+    pub fn describe(&self, points: &[Point]) -> String {
+        let label = |id| point_label(points, id);
+        match *self {
+            Constraint::Distance { a, b, angle } => {
+                format!("|{}–{}| = {:.2}°", label(a), label(b), angle.to_degrees())
+            }
+            Constraint::OnCircle { point, pole } => {
+                format!("{} on circle of pole {}", label(point), label(pole))
+            }
+            Constraint::EqualSides { a, b, c, d } => {
+                format!("|{}–{}| = |{}–{}|", label(a), label(b), label(c), label(d))
+            }
+            Constraint::RightAngle { vertex, a, b } => {
+                format!("∠{}–{}–{} = 90°", label(a), label(vertex), label(b))
+            }
+        }
+    }
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: Vec3) -> Option<Vec3> {
+    let len = dot(a, a).sqrt();
+    if len < 1e-10 {
+        None
+    } else {
+        Some([a[0] / len, a[1] / len, a[2] / len])
+    }
+}
+
+/// Unit tangent at `p` pointing along the great circle towards `towards`
+fn tangent_towards(p: Vec3, towards: Vec3) -> Option<Vec3> {
+    let d = dot(p, towards);
+    normalize([
+        towards[0] - d * p[0],
+        towards[1] - d * p[1],
+        towards[2] - d * p[2],
+    ])
+}
+
+/// Moves `p` by `angle` radians along the great circle towards `towards`
+fn move_towards(p: Vec3, towards: Vec3, angle: f64) -> Vec3 {
+    let Some(t) = tangent_towards(p, towards) else {
+        return p;
+    };
+    let (sin, cos) = angle.sin_cos();
+    [
+        p[0] * cos + t[0] * sin,
+        p[1] * cos + t[1] * sin,
+        p[2] * cos + t[2] * sin,
+    ]
+}
+
+/// Signed angle at `vertex` from the arc towards `a` to the arc towards `b`
+fn angle_at(vertex: Vec3, a: Vec3, b: Vec3) -> Option<f64> {
+    let ta = tangent_towards(vertex, a)?;
+    let tb = tangent_towards(vertex, b)?;
+    Some(dot(vertex, cross(ta, tb)).atan2(dot(ta, tb)))
+}
+
+/// Angular radius of the circle with pole `pole`, if such a circle exists
+fn circle_radius(
+    pole: usize,
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+) -> Option<f64> {
+    if let Some(sc) = small_circles.iter().find(|sc| sc.pole == pole) {
+        Some(sc.plane_distance.clamp(-1.0, 1.0).acos())
+    } else if great_circles.iter().any(|gc| gc.pole == pole) {
+        Some(std::f64::consts::FRAC_PI_2)
+    } else {
+        None
+    }
+}
+
+struct Solver<'a> {
+    positions: Vec<Vec3>,
+    free: Vec<bool>,
+    great_circles: &'a [GreatCircle],
+    small_circles: &'a [SmallCircle],
+}
+
+impl Solver<'_> {
+    /// Brings the arc `a`-`b` to length `target`, sharing the move between
+    /// whichever ends are free. Returns the error before the correction.
+    fn set_distance(&mut self, a: usize, b: usize, target: f64) -> f64 {
+        let (pa, pb) = (self.positions[a], self.positions[b]);
+        let error = arc_distance(pa, pb) - target;
+        match (self.free[a], self.free[b]) {
+            (true, true) => {
+                self.positions[a] = move_towards(pa, pb, error / 2.0);
+                self.positions[b] = move_towards(pb, pa, error / 2.0);
+            }
+            (true, false) => self.positions[a] = move_towards(pa, pb, error),
+            (false, true) => self.positions[b] = move_towards(pb, pa, error),
+            (false, false) => {}
+        }
+        error.abs()
+    }
+
+    /// Rotates `p` about the axis through `center` by `angle` radians
+    fn spin_about(&mut self, p: usize, center: usize, angle: f64) {
+        let rotation = Quaternion::from_axis_angle(self.positions[center], angle);
+        self.positions[p] = rotation.rotate_point_active(self.positions[p]);
+    }
+
+    /// Applies one correction step for `constraint`, returning its error before the step
+    fn project(&mut self, constraint: &Constraint) -> f64 {
+        match *constraint {
+            Constraint::Distance { a, b, angle } => self.set_distance(a, b, angle),
+            Constraint::OnCircle { point, pole } => {
+                match circle_radius(pole, self.great_circles, self.small_circles) {
+                    Some(radius) => self.set_distance(point, pole, radius),
+                    None => 0.0,
+                }
+            }
+            Constraint::EqualSides { a, b, c, d } => {
+                let first = arc_distance(self.positions[a], self.positions[b]);
+                let second = arc_distance(self.positions[c], self.positions[d]);
+                let first_free = self.free[a] || self.free[b];
+                let second_free = self.free[c] || self.free[d];
+                let target = match (first_free, second_free) {
+                    (true, true) => (first + second) / 2.0,
+                    (true, false) => second,
+                    (false, true) => first,
+                    (false, false) => return 0.0,
+                };
+                self.set_distance(a, b, target);
+                self.set_distance(c, d, target);
+                (first - second).abs()
+            }
+            Constraint::RightAngle { vertex, a, b } => {
+                let Some(angle) =
+                    angle_at(self.positions[vertex], self.positions[a], self.positions[b])
+                else {
+                    return 0.0;
+                };
+                let target = std::f64::consts::FRAC_PI_2.copysign(angle);
+                let error = target - angle;
+                // Swing the legs about the vertex, keeping their lengths
+                match (self.free[a], self.free[b]) {
+                    (true, true) => {
+                        self.spin_about(a, vertex, -error / 2.0);
+                        self.spin_about(b, vertex, error / 2.0);
+                    }
+                    (true, false) => self.spin_about(a, vertex, -error),
+                    (false, true) => self.spin_about(b, vertex, error),
+                    (false, false) => {}
+                }
+                error.abs()
+            }
+        }
+    }
+}
+
+/// Adjusts free points so that all constraints hold again after the points in
+/// `pinned` were moved.
+///
+/// Pinned, non-movable and derived points are never moved by the solver, nor are
+/// members of `groups`, which would lose their rigid layout if moved one by one.
+pub fn solve_constraints(
+    points: &mut [Point],
+    constraints: &[Constraint],
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+    groups: &[Vec<usize>],
+    pinned: &[usize],
+    q: Quaternion,
+) {
+    if constraints.is_empty() {
+        return;
+    }
+
+    let mut solver = Solver {
+        positions: points.iter().map(|p| p.absolute).collect(),
+        free: points
+            .iter()
+            .map(|p| {
+                p.movable
+                    && p.construction.is_none()
+                    && !pinned.contains(&p.id)
+                    && !groups.iter().any(|group| group.contains(&p.id))
+            })
+            .collect(),
+        great_circles,
+        small_circles,
+    };
+
+    for _ in 0..SOLVER_ITERATIONS {
+        let mut worst = 0.0f64;
+        for constraint in constraints {
+            worst = worst.max(solver.project(constraint));
+        }
+        if worst < SOLVER_TOLERANCE {
+            break;
+        }
+    }
+
+    for (point, position) in points.iter_mut().zip(solver.positions) {
+        if point.absolute != position {
+            if let Some(position) = normalize(position) {
+                point.set_absolute(position, q);
+            }
+        }
+    }
+}
+
+/// Fixes constraint references after point `removed` was deleted with
+/// `swap_remove`, which moved the point at `moved_from` into its slot.
+/// Constraints on the removed point are dropped.
+pub fn fix_constraint_indices(
+    constraints: &mut Vec<Constraint>,
+    removed: usize,
+    moved_from: usize,
+) {
+    constraints.retain(|c| !c.points().contains(&removed));
+    for constraint in constraints.iter_mut() {
+        constraint.remap(moved_from, removed);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Definition of a derived point in terms of other points, by index.
+///
+/// Derived points are recomputed whenever the points they depend on move.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Construction {
+    /// The point diametrically opposite to a point
+    Antipode(usize),
+    /// The pole of the great circle through two points
+    GreatCirclePole(usize, usize),
+    /// The pole of the small circle through three points
+    SmallCirclePole(usize, usize, usize),
+    /// An intersection of the great circles with the two given poles
+    Intersection(usize, usize),
+}
+
+impl Construction {
+    pub fn parents(&self) -> Vec<usize> {
+        match *self {
+            Construction::Antipode(p) => vec![p],
+            Construction::GreatCirclePole(a, b) | Construction::Intersection(a, b) => vec![a, b],
+            Construction::SmallCirclePole(a, b, c) => vec![a, b, c],
+        }
+    }
+
+    pub fn depends_on(&self, id: usize) -> bool {
+        self.parents().contains(&id)
+    }
+
+    /// Position of the derived point, or `None` if the definition degenerates
+    pub fn compute(&self, points: &[Point]) -> Option<Vec3> {
+        match *self {
+            Construction::Antipode(p) => {
+                let [x, y, z] = points[p].absolute;
+                Some([-x, -y, -z])
+            }
+            // The intersections of two great circles are the poles of the great
+            // circle through their poles
+            Construction::GreatCirclePole(a, b) | Construction::Intersection(a, b) => {
+                Some(great_circle_pole(points[a].absolute, points[b].absolute))
+            }
+            Construction::SmallCirclePole(a, b, c) => {
+                small_circle_pole(points[a].absolute, points[b].absolute, points[c].absolute)
+            }
+        }
+    }
+
+    /// Replaces references to point `from` with `to`
+    pub fn remap(&mut self, from: usize, to: usize) {
+        let fix = |id: &mut usize| {
+            if *id == from {
+                *id = to;
+            }
+        };
+        match self {
+            Construction::Antipode(p) => fix(p),
+            Construction::GreatCirclePole(a, b) | Construction::Intersection(a, b) => {
+                fix(a);
+                fix(b);
+            }
+            Construction::SmallCirclePole(a, b, c) => {
+                fix(a);
+                fix(b);
+                fix(c);
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            Construction::Antipode(p) => format!("antipode of {p}"),
+            Construction::GreatCirclePole(a, b) => {
+                format!("pole of great circle through {a}, {b}")
+            }
+            Construction::SmallCirclePole(a, b, c) => {
+                format!("pole of small circle through {a}, {b}, {c}")
+            }
+            Construction::Intersection(a, b) => {
+                format!("intersection of great circles with poles {a}, {b}")
+            }
+        }
+    }
+}
+
+/// Pole of the great circle through `p1` and `p2`.
+///
+/// Coincident or antipodal points do not fix a circle; some pole perpendicular
+/// to `p1` is returned instead.
+pub fn great_circle_pole(p1: Vec3, p2: Vec3) -> Vec3 {
+    let cross = [
+        p1[1] * p2[2] - p1[2] * p2[1],
+        p1[2] * p2[0] - p1[0] * p2[2],
+        p1[0] * p2[1] - p1[1] * p2[0],
+    ];
+    let mag2 = cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2];
+    if mag2 < 1e-10 {
+        let perp = if p1[0].abs() < p1[1].abs() && p1[0].abs() < p1[2].abs() {
+            [0.0, -p1[2], p1[1]]
+        } else if p1[1].abs() < p1[2].abs() {
+            [p1[2], 0.0, -p1[0]]
+        } else {
+            [-p1[1], p1[0], 0.0]
+        };
+        let mag = (perp[0] * perp[0] + perp[1] * perp[1] + perp[2] * perp[2]).sqrt();
+        [perp[0] / mag, perp[1] / mag, perp[2] / mag]
+    } else {
+        let mag = mag2.sqrt();
+        [cross[0] / mag, cross[1] / mag, cross[2] / mag]
+    }
+}
+
+/// Pole of the small circle through three points, on the side of the smaller cap.
+///
+/// Returns `None` if the points are collinear in space.
+pub fn small_circle_pole(p1: Vec3, p2: Vec3, p3: Vec3) -> Option<Vec3> {
+    let v1 = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
+    let v2 = [p3[0] - p1[0], p3[1] - p1[1], p3[2] - p1[2]];
+    let normal = [
+        v1[1] * v2[2] - v1[2] * v2[1],
+        v1[2] * v2[0] - v1[0] * v2[2],
+        v1[0] * v2[1] - v1[1] * v2[0],
+    ];
+    let mag2 = normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2];
+    if mag2 < 1e-10 {
+        return None;
+    }
+    let mag = mag2.sqrt();
+    let n = [normal[0] / mag, normal[1] / mag, normal[2] / mag];
+    let dots = [
+        n[0] * p1[0] + n[1] * p1[1] + n[2] * p1[2],
+        n[0] * p2[0] + n[1] * p2[1] + n[2] * p2[2],
+        n[0] * p3[0] + n[1] * p3[1] + n[2] * p3[2],
+    ];
+    if dots.iter().filter(|&&d| d < 0.0).count() >= 2 {
+        Some([-n[0], -n[1], -n[2]])
+    } else {
+        Some(n)
+    }
+}
+
+/// Derived points in an order where every point comes after its parents
+fn construction_order(points: &[Point]) -> Vec<usize> {
+    fn visit(id: usize, points: &[Point], visited: &mut [bool], order: &mut Vec<usize>) {
+        if visited[id] {
+            return;
+        }
+        visited[id] = true;
+        if let Some(construction) = points[id].construction {
+            for parent in construction.parents() {
+                if parent < points.len() {
+                    visit(parent, points, visited, order);
+                }
+            }
+            order.push(id);
+        }
+    }
+
+    let mut visited = vec![false; points.len()];
+    let mut order = Vec::new();
+    for id in 0..points.len() {
+        visit(id, points, &mut visited, &mut order);
+    }
+    order
+}
+
+/// Recomputes every derived point and every small circle passing through a point
+pub fn update_constructions(
+    points: &mut [Point],
+    small_circles: &mut [SmallCircle],
+    q: Quaternion,
+) {
+    for id in construction_order(points) {
+        let Some(construction) = points[id].construction else {
+            continue;
+        };
+        if let Some(position) = construction.compute(points) {
+            points[id].set_absolute(position, q);
+        }
+    }
+
+    for sc in small_circles.iter_mut() {
+        if let Some(through) = sc.through {
+            let pole = points[sc.pole].absolute;
+            let point = points[through].absolute;
+            sc.plane_distance =
+                (pole[0] * point[0] + pole[1] * point[1] + pole[2] * point[2]).clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Fixes construction references after point `removed` was deleted with
+/// `swap_remove`, which moved the point at `moved_from` into its slot.
+///
+/// Anything defined in terms of the removed point keeps its current position
+/// but stops following it.
+pub fn fix_construction_indices(
+    points: &mut [Point],
+    small_circles: &mut [SmallCircle],
+    removed: usize,
+    moved_from: usize,
+) {
+    for point in points.iter_mut() {
+        if let Some(construction) = point.construction.as_mut() {
+            if construction.depends_on(removed) {
+                point.construction = None;
+            } else {
+                construction.remap(moved_from, removed);
+            }
+        }
+    }
+    for sc in small_circles.iter_mut() {
+        if sc.through == Some(removed) {
+            sc.through = None;
+        } else if sc.through == Some(moved_from) {
+            sc.through = Some(removed);
+        }
+    }
+}
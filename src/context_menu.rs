@@ -0,0 +1,339 @@
+use crate::*;
+
+/// How far from a line, in radians on the sphere, a right click still hits it
+const HIT_TOLERANCE: f64 = 0.03;
+
+/// Object a context menu was opened on, by index into its list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextTarget {
+    Point(usize),
+    Arc(usize),
+    GreatCircle(usize),
+    SmallCircle(usize),
+}
+
+/// Open context menu, at the viewport position it was requested at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextMenu {
+    pub x: f64,
+    pub y: f64,
+    pub target: ContextTarget,
+}
+
+/// Object drawn at the given viewport position. Points win over lines; among
+/// lines, the closest one is taken.
+pub fn object_at(
+    x: f64,
+    y: f64,
+    points: &[Point],
+    arcs: &[(usize, usize)],
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+    state: &State,
+) -> Option<ContextTarget> {
+    if let Selected::Existing(id) =
+        select_point(x, y, points.to_vec(), state.quaternion, state.projection)
+    {
+        return Some(ContextTarget::Point(id));
+    }
+    let position = transform_viewport_to_sphere(x, y, state.projection);
+    if position.iter().any(|c| c.is_nan()) {
+        return None;
+    }
+    let tolerance = HIT_TOLERANCE / state.zoom;
+
+    let arc_hits = arcs.iter().enumerate().filter_map(|(i, &(a, b))| {
+        let (a, b) = (points[a].rotated, points[b].rotated);
+        let pole = great_circle_pole(a, b);
+        let off_circle = dot(position, pole).clamp(-1.0, 1.0).asin().abs();
+        // Past either end, the way round through the click is longer than the arc
+        let detour = arc_distance(a, position) + arc_distance(position, b) - arc_distance(a, b);
+        (detour < tolerance).then_some((off_circle, ContextTarget::Arc(i)))
+    });
+    let great_circle_hits = great_circles.iter().enumerate().map(|(i, gc)| {
+        let pole = points[gc.pole].rotated;
+        let off_circle = dot(position, pole).clamp(-1.0, 1.0).asin().abs();
+        (off_circle, ContextTarget::GreatCircle(i))
+    });
+    let small_circle_hits = small_circles.iter().enumerate().map(|(i, sc)| {
+        let pole = points[sc.pole].rotated;
+        let radius = sc.plane_distance.clamp(-1.0, 1.0).acos();
+        let off_circle = (arc_distance(position, pole) - radius).abs();
+        (off_circle, ContextTarget::SmallCircle(i))
+    });
+
+    arc_hits
+        .chain(great_circle_hits)
+        .chain(small_circle_hits)
+        .filter(|&(off_circle, _)| off_circle < tolerance)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, target)| target)
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Actions for the object under the cursor: renaming, hiding, deleting and
+/// styling, and the constructions that start from it
+#[component]
+pub fn ContextMenuPanel(
+    menu: Signal<Option<ContextMenu>>,
+    mut points: Signal<Vec<Point>>,
+    mut arcs: Signal<Vec<(usize, usize)>>,
+    mut great_circles: Signal<Vec<GreatCircle>>,
+    mut small_circles: Signal<Vec<SmallCircle>>,
+    mut state: Signal<State>,
+) -> Element {
+    let Some(ContextMenu { x, y, target }) = menu() else {
+        return rsx! {};
+    };
+    let mut close = move || {
+        menu.set(None);
+        // Give the keyboard back to the sphere
+        document::eval("document.getElementById('sphere')?.focus();");
+    };
+    // Runs a keyboard action on the point alone, then closes the menu
+    let mut on_point = move |id: usize, action: KeyAction| {
+        {
+            let mut s = state.write();
+            s.clear_selection();
+            s.select(id);
+        }
+        run_key_action(action, points, arcs, great_circles, small_circles, state);
+        close();
+    };
+    let mut select_only = move |ids: &[usize]| {
+        let mut s = state.write();
+        s.clear_selection();
+        for &id in ids {
+            s.select(id);
+        }
+    };
+
+    // The lists may have changed under the menu, e.g. by a shortcut or a loaded file
+    let valid = match target {
+        ContextTarget::Point(id) => id < points.read().len(),
+        ContextTarget::Arc(i) => i < arcs.read().len(),
+        ContextTarget::GreatCircle(i) => i < great_circles.read().len(),
+        ContextTarget::SmallCircle(i) => i < small_circles.read().len(),
+    };
+    if !valid {
+        return rsx! {};
+    }
+
+    let items = match target {
+        ContextTarget::Point(id) => {
+            let point = points.read()[id].clone();
+            let color = point.color.clone().unwrap_or_else(|| "#ff0000".to_string());
+            let has_great_circle = great_circles.read().iter().any(|gc| gc.pole == id);
+            // Hiding and deleting act on the whole group, like their shortcuts
+            let grouped = state.read().find_group_containing(id).is_some();
+            rsx! {
+                div { class: "context-menu-title", "Point {point_label(&points.read(), id)}" }
+                label { class: "context-menu-field",
+                    "Name"
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        value: "{point.name}",
+                        oninput: move |evt| points.write()[id].name = evt.value(),
+                    }
+                }
+                label { class: "context-menu-field",
+                    "Color"
+                    input {
+                        r#type: "color",
+                        value: "{color}",
+                        oninput: move |evt| points.write()[id].color = Some(evt.value()),
+                    }
+                }
+                button {
+                    disabled: point.color.is_none(),
+                    onclick: move |_| points.write()[id].color = None,
+                    "Default color"
+                }
+                button { onclick: move |_| on_point(id, KeyAction::ToggleHidden),
+                    match (point.hidden, grouped) {
+                        (true, true) => "Show group",
+                        (true, false) => "Show",
+                        (false, true) => "Hide group",
+                        (false, false) => "Hide",
+                    }
+                }
+                button {
+                    title: "Locked points cannot be dragged",
+                    onclick: move |_| {
+                        let movable = points.read()[id].movable;
+                        points.write()[id].movable = !movable;
+                        close();
+                    },
+                    if point.movable {
+                        "Lock"
+                    } else {
+                        "Unlock"
+                    }
+                }
+                button { onclick: move |_| on_point(id, KeyAction::GreatCircleAroundPole),
+                    if has_great_circle {
+                        "Remove great circle around it"
+                    } else {
+                        "Draw great circle around it"
+                    }
+                }
+                button { onclick: move |_| on_point(id, KeyAction::Antipode), "Add antipode" }
+                button {
+                    disabled: !point.removable,
+                    title: if !point.removable { "This point cannot be removed" },
+                    onclick: move |_| on_point(id, KeyAction::DeleteSelected),
+                    if grouped {
+                        "Delete group"
+                    } else {
+                        "Delete"
+                    }
+                }
+            }
+        }
+        ContextTarget::Arc(i) => {
+            let (a, b) = arcs.read()[i];
+            rsx! {
+                div { class: "context-menu-title",
+                    "Arc {point_label(&points.read(), a)}–{point_label(&points.read(), b)}"
+                }
+                button {
+                    onclick: move |_| {
+                        select_only(&[a, b]);
+                        close();
+                    },
+                    "Select ends"
+                }
+                button {
+                    onclick: move |_| {
+                        arcs.write().remove(i);
+                        close();
+                    },
+                    "Delete"
+                }
+            }
+        }
+        ContextTarget::GreatCircle(i) => {
+            let gc = great_circles.read()[i].clone();
+            let color = gc.color.clone().unwrap_or_else(|| "#00ff00".to_string());
+            rsx! {
+                div { class: "context-menu-title",
+                    "Great circle around {point_label(&points.read(), gc.pole)}"
+                }
+                label { class: "context-menu-field",
+                    "Name"
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        value: "{gc.name}",
+                        oninput: move |evt| great_circles.write()[i].name = evt.value(),
+                    }
+                }
+                label { class: "context-menu-field",
+                    "Color"
+                    input {
+                        r#type: "color",
+                        value: "{color}",
+                        oninput: move |evt| great_circles.write()[i].color = Some(evt.value()),
+                    }
+                }
+                button {
+                    disabled: gc.color.is_none(),
+                    onclick: move |_| great_circles.write()[i].color = None,
+                    "Default color"
+                }
+                button {
+                    onclick: move |_| {
+                        select_only(&[gc.pole]);
+                        close();
+                    },
+                    "Select pole"
+                }
+                button {
+                    onclick: move |_| {
+                        remove_great_circle(
+                            &mut great_circles.write(),
+                            &mut state.write().grid,
+                            gc.pole,
+                        );
+                        close();
+                    },
+                    "Delete"
+                }
+            }
+        }
+        ContextTarget::SmallCircle(i) => {
+            let sc = small_circles.read()[i].clone();
+            let color = sc.color.clone().unwrap_or_else(|| "#00ffff".to_string());
+            rsx! {
+                div { class: "context-menu-title",
+                    "Small circle around {point_label(&points.read(), sc.pole)}"
+                }
+                label { class: "context-menu-field",
+                    "Name"
+                    input {
+                        r#type: "text",
+                        spellcheck: "false",
+                        value: "{sc.name}",
+                        oninput: move |evt| small_circles.write()[i].name = evt.value(),
+                    }
+                }
+                label { class: "context-menu-field",
+                    "Color"
+                    input {
+                        r#type: "color",
+                        value: "{color}",
+                        oninput: move |evt| small_circles.write()[i].color = Some(evt.value()),
+                    }
+                }
+                button {
+                    disabled: sc.color.is_none(),
+                    onclick: move |_| small_circles.write()[i].color = None,
+                    "Default color"
+                }
+                button {
+                    onclick: move |_| {
+                        select_only(&[sc.pole]);
+                        close();
+                    },
+                    "Select pole"
+                }
+                button {
+                    onclick: move |_| {
+                        small_circles.write().remove(i);
+                        close();
+                    },
+                    "Delete"
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div {
+            class: "context-menu-backdrop",
+            onmousedown: move |_| close(),
+            oncontextmenu: move |evt| {
+                evt.prevent_default();
+                close();
+            },
+        }
+        div {
+            class: "context-menu",
+            role: "menu",
+            style: "left: {x}px; top: {y}px;",
+            oncontextmenu: move |evt| evt.prevent_default(),
+            // Typing in the name field must not reach the sphere's shortcuts
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                if matches!(evt.key(), Key::Escape | Key::Enter) {
+                    close();
+                }
+            },
+            {items}
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::*;
+
+/// Rows of a CSV file, with the first line taken as column headers
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CsvTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Splits CSV text into fields. Fields may be quoted, with `""` for a quote inside;
+/// the delimiter is a comma, or a semicolon or tab if the header line uses those
+/// instead, as spreadsheets in some locales write.
+pub fn parse_csv(text: &str) -> Result<CsvTable, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let header_line = text.lines().next().unwrap_or_default();
+    let delimiter = [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|&d| header_line.matches(d).count())
+        .filter(|&d| header_line.contains(d))
+        .unwrap_or(',');
+
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            _ if quoted => field.push(c),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("A quoted field is not closed".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    let mut records = records
+        .into_iter()
+        .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+        .map(|record| record.into_iter().map(|f| f.trim().to_string()).collect());
+    let headers: Vec<String> = records.next().ok_or("The file is empty")?;
+    Ok(CsvTable {
+        headers,
+        rows: records.collect(),
+    })
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', ';', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Parses an angle written as a decimal number or in sexagesimal notation, such as
+/// `12:34:56.7`, `12h34m56.7s`, `-5 23 45` or `-5°23'45"`. The result is in the
+/// unit of the first component (hours or degrees).
+pub fn parse_sexagesimal(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let negative = text.starts_with('-') || text.starts_with('−');
+    let unsigned = text.strip_prefix(['-', '−', '+']).unwrap_or(text);
+    // A second sign would otherwise be read as a separator
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    let parts: Vec<f64> = unsigned
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if parts.is_empty() || parts.len() > 3 || parts[1..].iter().any(|&p| p >= 60.0) {
+        return None;
+    }
+    let value = parts
+        .iter()
+        .zip([1.0, 60.0, 3600.0])
+        .map(|(part, divisor)| part / divisor)
+        .sum::<f64>();
+    Some(if negative { -value } else { value })
+}
+
+/// Writes an angle as `d:mm:ss.s`, or `+d:mm:ss.s` if `signed`. Rounding is done
+/// on tenths of a second, so it carries into the minutes and degrees.
+pub fn format_sexagesimal(value: f64, signed: bool) -> String {
+    let tenths = (value.abs() * 36000.0).round() as u64;
+    let sign = match (value < 0.0 && tenths > 0, signed) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    format!("{sign}{}", format_tenths(tenths))
+}
+
+/// Writes a right ascension in hours as `h:mm:ss.s`, within `[0h, 24h)`: an angle
+/// that rounds up to 24h is written as 0h
+pub fn format_right_ascension(hours: f64) -> String {
+    const DAY: u64 = 24 * 36000;
+    let tenths = (hours.rem_euclid(24.0) * 36000.0).round() as u64 % DAY;
+    format_tenths(tenths)
+}
+
+fn format_tenths(tenths: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}.{}",
+        tenths / 36000,
+        tenths / 600 % 60,
+        tenths / 10 % 60,
+        tenths % 10
+    )
+}
+
+/// Coordinates the columns of an imported file are read as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvCoordinates {
+    /// Latitude and longitude in degrees, in the frame of the coordinate grid
+    #[default]
+    LatLon,
+    /// Right ascension and declination in degrees, in the frame of the coordinate grid
+    RaDecDegrees,
+    /// Right ascension in hours and declination in degrees, both sexagesimal
+    RaDecSexagesimal,
+    /// Cartesian coordinates, as shown in the selection box; they need not be normalized
+    Cartesian,
+}
+
+impl CsvCoordinates {
+    pub const ALL: [CsvCoordinates; 4] = [
+        CsvCoordinates::LatLon,
+        CsvCoordinates::RaDecDegrees,
+        CsvCoordinates::RaDecSexagesimal,
+        CsvCoordinates::Cartesian,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CsvCoordinates::LatLon => "Latitude / longitude (°)",
+            CsvCoordinates::RaDecDegrees => "RA / Dec (°)",
+            CsvCoordinates::RaDecSexagesimal => "RA (h:m:s) / Dec (d:m:s)",
+            CsvCoordinates::Cartesian => "x / y / z",
+        }
+    }
+
+    /// Labels of the columns the coordinates are read from
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            CsvCoordinates::LatLon => &["Latitude", "Longitude"],
+            CsvCoordinates::RaDecDegrees | CsvCoordinates::RaDecSexagesimal => &["RA", "Dec"],
+            CsvCoordinates::Cartesian => &["x", "y", "z"],
+        }
+    }
+
+    /// Header names recognized for each column, in lower case
+    fn header_names(self) -> &'static [&'static [&'static str]] {
+        match self {
+            CsvCoordinates::LatLon => &[
+                &["lat", "latitude", "b"],
+                &["lon", "long", "longitude", "l"],
+            ],
+            CsvCoordinates::RaDecDegrees | CsvCoordinates::RaDecSexagesimal => &[
+                &["ra", "right ascension", "alpha", "ra_deg", "ra (deg)"],
+                &["dec", "declination", "delta", "de", "dec_deg", "dec (deg)"],
+            ],
+            CsvCoordinates::Cartesian => &[&["x"], &["y"], &["z"]],
+        }
+    }
+}
+
+/// First column whose header is one of `names`, trying the names in order
+fn header_column(table: &CsvTable, names: &[&str]) -> Option<usize> {
+    names.iter().find_map(|name| {
+        table
+            .headers
+            .iter()
+            .position(|header| header.to_lowercase() == *name)
+    })
+}
+
+/// Which columns of a CSV file hold the names and coordinates of the points
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CsvImport {
+    pub coordinates: CsvCoordinates,
+    /// Column of the point names, if any
+    pub name: Option<usize>,
+    /// Columns of the coordinates, in the order of `CsvCoordinates::columns`
+    pub columns: [Option<usize>; 3],
+}
+
+impl CsvImport {
+    /// Picks the coordinates and columns from the header names where possible
+    pub fn guess(table: &CsvTable) -> Self {
+        let find = |names: &[&str]| header_column(table, names);
+        let coordinates = [
+            CsvCoordinates::LatLon,
+            CsvCoordinates::RaDecDegrees,
+            CsvCoordinates::Cartesian,
+        ]
+        .into_iter()
+        .find(|coordinates| {
+            coordinates
+                .header_names()
+                .iter()
+                .all(|names| find(names).is_some())
+        })
+        .unwrap_or_default();
+        let mut import = CsvImport {
+            coordinates,
+            name: find(&["name", "label", "star", "id", "designation"]),
+            columns: [None; 3],
+        };
+        import.set_coordinates(table, coordinates);
+
+        // Sexagesimal right ascensions contain separators
+        if coordinates == CsvCoordinates::RaDecDegrees {
+            let sexagesimal = import.columns[0].is_some_and(|column| {
+                table.rows.iter().any(|row| {
+                    row.get(column)
+                        .is_some_and(|value| value.parse::<f64>().is_err())
+                })
+            });
+            if sexagesimal {
+                import.coordinates = CsvCoordinates::RaDecSexagesimal;
+            }
+        }
+        import
+    }
+
+    /// Switches to other coordinates, guessing their columns from the headers
+    pub fn set_coordinates(&mut self, table: &CsvTable, coordinates: CsvCoordinates) {
+        self.coordinates = coordinates;
+        self.columns = [None; 3];
+        for (column, names) in self.columns.iter_mut().zip(coordinates.header_names()) {
+            *column = header_column(table, names);
+        }
+    }
+
+    /// Names and absolute positions of the points in the table, and a message
+    /// for each row that could not be read
+    pub fn read(
+        &self,
+        table: &CsvTable,
+        grid: &GridConfig,
+        points: &[Point],
+    ) -> (Vec<(String, Vec3)>, Vec<String>) {
+        let columns = self.coordinates.columns();
+        let mut read = Vec::new();
+        let mut errors = Vec::new();
+
+        if let Some(missing) = columns
+            .iter()
+            .zip(self.columns)
+            .find(|(_, column)| column.is_none())
+        {
+            errors.push(format!("No column is chosen for {}", missing.0));
+            return (read, errors);
+        }
+
+        for (i, row) in table.rows.iter().enumerate() {
+            // Line numbers as shown by spreadsheets, counting the header line
+            let line = i + 2;
+            let values: Vec<Option<f64>> = columns
+                .iter()
+                .enumerate()
+                .map(|(j, _)| {
+                    let text = self.columns[j].and_then(|column| row.get(column))?;
+                    match self.coordinates {
+                        CsvCoordinates::RaDecSexagesimal => parse_sexagesimal(text),
+                        _ => text.parse::<f64>().ok(),
+                    }
+                    .filter(|value| value.is_finite())
+                })
+                .collect();
+            let Some(values) = values.into_iter().collect::<Option<Vec<f64>>>() else {
+                errors.push(format!("Line {line}: the coordinates are not numbers"));
+                continue;
+            };
+
+            let position = match self.coordinates {
+                CsvCoordinates::LatLon => (values[0].abs() <= 90.0)
+                    .then(|| grid.frame_position(points, [values[0], values[1]])),
+                CsvCoordinates::RaDecDegrees => (values[1].abs() <= 90.0)
+                    .then(|| grid.frame_position(points, [values[1], values[0]])),
+                CsvCoordinates::RaDecSexagesimal => (values[1].abs() <= 90.0)
+                    .then(|| grid.frame_position(points, [values[1], values[0] * 15.0])),
+                CsvCoordinates::Cartesian => {
+                    let length = values.iter().map(|v| v * v).sum::<f64>().sqrt();
+                    // The selection box shows y pointing up, unlike the screen
+                    (length > 1e-9)
+                        .then(|| [values[0] / length, -values[1] / length, values[2] / length])
+                }
+            };
+            let Some(position) = position else {
+                errors.push(format!("Line {line}: the position is outside the sphere"));
+                continue;
+            };
+            let name = self
+                .name
+                .and_then(|column| row.get(column))
+                .cloned()
+                .unwrap_or_default();
+            read.push((name, position));
+        }
+        (read, errors)
+    }
+}
+
+/// Lists the points with their coordinates: Cartesian as shown in the selection box,
+/// and latitude/longitude and RA/Dec in the frame of the coordinate grid
+pub fn points_to_csv(points: &[Point], grid: &GridConfig) -> String {
+    let mut csv = "id,name,x,y,z,lat,lon,ra,dec,hidden,defined_as\n".to_string();
+    for point in points {
+        let [x, y, z] = point.absolute;
+        let [lat, lon] = grid.frame_coordinates(points, point.absolute);
+        let definition = point
+            .construction
+            .map(|construction| construction.describe())
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{x:.6},{:.6},{z:.6},{lat:.6},{lon:.6},{},{},{},{}\n",
+            point.id,
+            csv_field(&point.name),
+            // Avoids writing -0
+            0.0 - y,
+            format_right_ascension(lon / 15.0),
+            format_sexagesimal(lat, true),
+            point.hidden,
+            csv_field(&definition)
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn quoted_fields() {
+        let table = parse_csv("name,note\n\"Smith, J.\",\"said \"\"hi\"\"\nand left\"\n").unwrap();
+        assert_eq!(table.headers, strings(&["name", "note"]));
+        assert_eq!(
+            table.rows,
+            vec![strings(&["Smith, J.", "said \"hi\"\nand left"])]
+        );
+    }
+
+    #[test]
+    fn semicolons_tabs_and_line_endings() {
+        let table = parse_csv("lat;lon\r\n1,5;2\r\n\r\n3;4").unwrap();
+        assert_eq!(table.headers, strings(&["lat", "lon"]));
+        assert_eq!(
+            table.rows,
+            vec![strings(&["1,5", "2"]), strings(&["3", "4"])]
+        );
+        let table = parse_csv("a\tb\n1\t2\n").unwrap();
+        assert_eq!(table.rows, vec![strings(&["1", "2"])]);
+    }
+
+    #[test]
+    fn byte_order_mark_is_skipped() {
+        let table = parse_csv("\u{feff}name,ra,dec\nVega,18:36:56,+38:47:01\n").unwrap();
+        assert_eq!(table.headers[0], "name");
+        let import = CsvImport::guess(&table);
+        assert_eq!(import.coordinates, CsvCoordinates::RaDecSexagesimal);
+        assert_eq!(import.name, Some(0));
+        assert_eq!(import.columns, [Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn malformed_files() {
+        assert_eq!(
+            parse_csv("a,b\n\"open,2\n").unwrap_err(),
+            "A quoted field is not closed"
+        );
+        assert_eq!(parse_csv("\n , \n").unwrap_err(), "The file is empty");
+    }
+
+    #[test]
+    fn sexagesimal_notations() {
+        let close = |text: &str, value: f64| {
+            let parsed = parse_sexagesimal(text).unwrap_or(f64::NAN);
+            assert!((parsed - value).abs() < 1e-9, "{text}: {parsed} != {value}");
+        };
+        let expected = 12.0 + 34.0 / 60.0 + 56.7 / 3600.0;
+        close("12:34:56.7", expected);
+        close("12h34m56.7s", expected);
+        close("12 34 56.7", expected);
+        close("-5°23'45\"", -(5.0 + 23.0 / 60.0 + 45.0 / 3600.0));
+        // The sign applies to the whole angle, even with zero degrees
+        close("−0:30", -0.5);
+        close("+7.25", 7.25);
+        for invalid in ["", "abc", "12:60:00", "1:2:3:4", "1:2:60", "--1"] {
+            assert_eq!(parse_sexagesimal(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn sexagesimal_rounding_carries() {
+        assert_eq!(format_sexagesimal(0.0, false), "0:00:00.0");
+        assert_eq!(format_sexagesimal(0.0, true), "+0:00:00.0");
+        // Too small to show: no minus sign before zero
+        assert_eq!(format_sexagesimal(-1e-9, true), "+0:00:00.0");
+        assert_eq!(format_sexagesimal(-12.5, true), "-12:30:00.0");
+        // 59.99 seconds round up into the next minute and degree
+        assert_eq!(format_sexagesimal(1.0 - 0.01 / 3600.0, false), "1:00:00.0");
+        assert_eq!(format_sexagesimal(90.0, true), "+90:00:00.0");
+    }
+
+    #[test]
+    fn right_ascensions_stay_below_24h() {
+        assert_eq!(format_right_ascension(23.999_999_9), "0:00:00.0");
+        assert_eq!(format_right_ascension(24.0), "0:00:00.0");
+        assert_eq!(format_right_ascension(-1.0), "23:00:00.0");
+        assert_eq!(
+            format_right_ascension(23.0 + 59.0 / 60.0 + 59.94 / 3600.0),
+            "23:59:59.9"
+        );
+    }
+
+    #[test]
+    fn formatted_angles_parse_back() {
+        for value in [0.0, 0.5, 12.345_678, -45.999_99, 89.999] {
+            let parsed = parse_sexagesimal(&format_sexagesimal(value, true)).unwrap();
+            assert!((parsed - value).abs() <= 0.05 / 3600.0 + 1e-12, "{value}");
+        }
+    }
+
+    #[test]
+    fn exported_points_import_again() {
+        let grid = GridConfig::default();
+        let points: Vec<Point> = [[0.3, 0.4, 0.866], [0.0, -1.0, 0.0], [1.0, -1e-7, 0.0]]
+            .into_iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let length = position.iter().map(|c: &f64| c * c).sum::<f64>().sqrt();
+                let mut point = Point::from_vec3(i, position.map(|c| c / length));
+                point.name = format!("P, \"{i}\"");
+                point
+            })
+            .collect();
+        let table = parse_csv(&points_to_csv(&points, &grid)).unwrap();
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[0][1], "P, \"0\"");
+        // Just below 360° of longitude: written as 0h, not 24h
+        assert_eq!(table.rows[2][7], "0:00:00.0");
+
+        let mut import = CsvImport::guess(&table);
+        assert_eq!(import.coordinates, CsvCoordinates::LatLon);
+        for coordinates in [
+            CsvCoordinates::LatLon,
+            CsvCoordinates::RaDecSexagesimal,
+            CsvCoordinates::Cartesian,
+        ] {
+            import.set_coordinates(&table, coordinates);
+            let (read, errors) = import.read(&table, &grid, &points);
+            assert!(errors.is_empty(), "{errors:?}");
+            for ((name, position), point) in read.iter().zip(&points) {
+                assert_eq!(name, &point.name);
+                assert!(
+                    arc_distance(*position, point.absolute) < 1e-5,
+                    "{coordinates:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unreadable_rows_are_reported() {
+        let table = parse_csv("lat,lon\n95,0\nx,1\n10,20\n").unwrap();
+        let (read, errors) = CsvImport::guess(&table).read(&table, &GridConfig::default(), &[]);
+        assert_eq!(read.len(), 1);
+        assert_eq!(
+            errors,
+            strings(&[
+                "Line 2: the position is outside the sphere",
+                "Line 3: the coordinates are not numbers"
+            ])
+        );
+    }
+}
@@ -1,5 +1,13 @@
 use crate::circle::*;
+use crate::constraint::*;
+use crate::construction::*;
+use crate::context_menu::*;
+use crate::keymap::*;
+use crate::platform;
 use crate::point::*;
+use crate::projection::*;
+use crate::tool::*;
+use crate::view::*;
 use crate::State;
 use dioxus::prelude::*;
 
@@ -10,18 +18,28 @@ pub enum Selected {
     None,
 }
 
-pub fn select_point(x: f64, y: f64, points: Vec<Point>, state: Quaternion) -> Selected {
-    let [px, py, pz] = transform_viewport_to_sphere(x, y);
-    if pz.is_nan() {
+pub fn select_point(
+    x: f64,
+    y: f64,
+    points: Vec<Point>,
+    state: Quaternion,
+    projection: Projection,
+) -> Selected {
+    let Some((vx, vy)) = transform_viewport_to_disc(x, y) else {
         return Selected::None;
-    }
+    };
+    let Some([px, py, pz]) = projection.unproject(vx, vy) else {
+        return Selected::None;
+    };
     for p in points.iter() {
-        let [x, y, z] = p.rotated;
-        if z < 0.0 {
+        if !projection.is_front(p.rotated) {
             continue;
         }
-        let dx = px - x;
-        let dy = py - y;
+        let Some([x, y]) = projection.project(p.rotated) else {
+            continue;
+        };
+        let dx = vx - x;
+        let dy = vy - y;
         if dx.powi(2) + dy.powi(2) <= 0.002 {
             return Selected::Existing(p.id);
         }
@@ -29,24 +47,30 @@ pub fn select_point(x: f64, y: f64, points: Vec<Point>, state: Quaternion) -> Se
     Selected::New(Point::from_vec3_rotated(points.len(), [px, py, pz], state))
 }
 
+/// Left click on the sphere, handled according to the current tool
 pub fn handle_primary_click(
     event: Event<MouseData>,
     mut points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
     great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
     mut state: Signal<State>,
     mut dragged_point: Signal<Option<usize>>,
 ) {
     let multi = event.modifiers().shift();
+    let tool = state.read().tool;
     let scale_val = state.read().quaternion;
+    let projection = state.read().projection;
     match select_point(
         event.client_coordinates().x,
         event.client_coordinates().y,
         points(),
         scale_val,
+        projection,
     ) {
         Selected::None => (),
-        Selected::New(mut point) => {
-            if event.modifiers().shift() {
+        Selected::New(mut point) if tool == Tool::Point => {
+            if state.read().snap {
                 let threshold = 0.05;
                 let snapped =
                     snap_to_great_circle(point.rotated, &great_circles(), &points(), threshold);
@@ -59,28 +83,62 @@ pub fn handle_primary_click(
                 state.write().toggle_select_group(multi, points().len() - 1);
             }
         }
-        Selected::Existing(selected) => {
-            if state.write().toggle_select_group(multi, selected) && points()[selected].movable {
+        Selected::New(_) => state.write().clear_selection(),
+        Selected::Existing(selected) if matches!(tool, Tool::Select | Tool::Point) => {
+            // Derived points follow their construction and cannot be dragged
+            if state.write().toggle_select_group(multi, selected)
+                && points()[selected].movable
+                && points()[selected].construction.is_none()
+            {
                 dragged_point.set(Some(selected));
             }
         }
+        Selected::Existing(selected) => {
+            use_tool_on_point(selected, points, arcs, great_circles, small_circles, state)
+        }
     }
 }
 
+/// Right click: opens the context menu of the object under the cursor. With
+/// Alt, draws or removes arcs between the selection and the clicked point.
 pub fn handle_secondary_click(
     event: Event<MouseData>,
     points: Signal<Vec<Point>>,
     mut arcs: Signal<Vec<(usize, usize)>>,
-    state: Signal<State>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    mut state: Signal<State>,
+    mut context_menu: Signal<Option<ContextMenu>>,
 ) {
+    if !event.modifiers().alt() {
+        let (x, y) = (event.client_coordinates().x, event.client_coordinates().y);
+        let target = object_at(
+            x,
+            y,
+            &points.read(),
+            &arcs.read(),
+            &great_circles.read(),
+            &small_circles.read(),
+            &state.read(),
+        );
+        if let Some(ContextTarget::Point(id)) = target {
+            let mut s = state.write();
+            s.clear_selection();
+            s.select(id);
+        }
+        context_menu.set(target.map(|target| ContextMenu { x, y, target }));
+        return;
+    }
     if state.read().selected().is_empty() {
         return;
     }
+    let projection = state.read().projection;
     match select_point(
         event.client_coordinates().x,
         event.client_coordinates().y,
         points(),
         Quaternion::identity(),
+        projection,
     ) {
         Selected::None => (),
         Selected::New(_) => {}
@@ -103,9 +161,13 @@ pub fn handle_secondary_click(
 
 pub fn handle_middle_click(
     event: Event<MouseData>,
+    mut state: Signal<State>,
     mut is_rotating: Signal<bool>,
     mut last_rotation_pos: Signal<(f64, f64)>,
+    mut spin: Signal<(Vec3, f64, f64)>,
 ) {
+    state.write().cancel_view_animation();
+    spin.set(([0.0, 0.0, 1.0], 0.0, platform::now_ms()));
     is_rotating.set(true);
     last_rotation_pos.set((event.client_coordinates().x, event.client_coordinates().y));
     event.prevent_default();
@@ -119,26 +181,30 @@ pub fn handle_scroll(event: Event<WheelData>, mut state: Signal<State>) {
     state.write().zoom = new_scale;
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_mouse_move(
     event: Event<MouseData>,
     mut points: Signal<Vec<Point>>,
     great_circles: Signal<Vec<GreatCircle>>,
+    mut small_circles: Signal<Vec<SmallCircle>>,
     mut state: Signal<State>,
     dragged_point: Signal<Option<usize>>,
     is_rotating: Signal<bool>,
     mut last_rotation_pos: Signal<(f64, f64)>,
+    mut spin: Signal<(Vec3, f64, f64)>,
 ) {
     if let Some(dragged_idx) = dragged_point() {
         let viewport_x = event.client_coordinates().x;
         let viewport_y = event.client_coordinates().y;
-        let [px, py, pz] = transform_viewport_to_sphere(viewport_x, viewport_y);
+        let projection = state.read().projection;
+        let [px, py, pz] = transform_viewport_to_sphere(viewport_x, viewport_y, projection);
         if pz.is_nan() {
             return;
         }
 
         let group_members = state.read().get_group_members(dragged_idx);
         let original_pos = points()[dragged_idx].rotated;
-        let new_pos = if event.modifiers().shift() {
+        let new_pos = if state.read().snap || event.modifiers().shift() {
             let threshold = 0.05;
             snap_to_great_circle([px, py, pz], &great_circles(), &points(), threshold)
         } else {
@@ -177,12 +243,27 @@ pub fn handle_mouse_move(
 
                 // Apply rotation to all group members
                 for &member_idx in &group_members {
-                    if points()[member_idx].movable {
+                    if points()[member_idx].movable && points()[member_idx].construction.is_none() {
                         let current_rotated = points()[member_idx].rotated;
                         let new_rotated = rotation_quat.rotate_point_active(current_rotated);
                         points.write()[member_idx].move_to(new_rotated, state.read().quaternion);
                     }
                 }
+
+                let q = state.read().quaternion;
+                let constraints = state.read().constraints.clone();
+                update_constructions(&mut points.write(), &mut small_circles.write(), q);
+                solve_constraints(
+                    &mut points.write(),
+                    &constraints,
+                    &great_circles.read(),
+                    &small_circles.read(),
+                    &state.read().groups,
+                    &group_members,
+                    q,
+                );
+                update_constructions(&mut points.write(), &mut small_circles.write(), q);
+                record_traces(&mut state.write().traces, &points.read());
             }
         }
 
@@ -195,61 +276,145 @@ pub fn handle_mouse_move(
         let current_x = event.client_coordinates().x;
         let current_y = event.client_coordinates().y;
         let (last_x, last_y) = last_rotation_pos();
-        let sensitivity = 0.005;
-        let delta_x = (current_x - last_x) * sensitivity;
-        let delta_y = -(current_y - last_y) * sensitivity;
-        let rotation_y = Quaternion::from_axis_angle([1.0, 0.0, 0.0], delta_y);
-        let rotation_x = Quaternion::from_axis_angle([0.0, 1.0, 0.0], delta_x);
-        let new_rotation = rotation_y
-            .multiply(rotation_x)
-            .multiply(state.read().quaternion);
+        let from = transform_viewport_to_arcball(last_x, last_y);
+        let to = transform_viewport_to_arcball(current_x, current_y);
+        if from[2].is_nan() || to[2].is_nan() {
+            return;
+        }
+        // Ctrl rolls the sphere about the view axis, otherwise the grabbed spot
+        // follows the cursor
+        let drag = if event.modifiers().ctrl() {
+            let angle = to[1].atan2(to[0]) - from[1].atan2(from[0]);
+            Quaternion::from_axis_angle([0.0, 0.0, 1.0], angle)
+        } else {
+            Quaternion::from_two_vectors(from, to)
+        };
+        let new_rotation = drag.multiply(state.read().quaternion).normalize();
         state.write().quaternion = new_rotation;
         state.write().rotation = new_rotation.to_euler_deg();
         last_rotation_pos.set((current_x, current_y));
         for point in points.write().iter_mut() {
             point.rotate(new_rotation);
         }
+
+        let now = platform::now_ms();
+        let (axis, angle) = drag.to_axis_angle();
+        let dt = now - spin().2;
+        let speed = if dt > 0.0 { angle / dt } else { 0.0 };
+        spin.set((axis, speed, now));
     }
 }
 
 pub fn handle_mouse_up(
     _event: Event<MouseData>,
+    points: Signal<Vec<Point>>,
+    state: Signal<State>,
     mut dragged_point: Signal<Option<usize>>,
     mut is_rotating: Signal<bool>,
+    spin: Signal<(Vec3, f64, f64)>,
 ) {
     dragged_point.set(None);
+    if is_rotating() && state.read().rotation_inertia {
+        let (axis, speed, last_move) = spin();
+        if platform::now_ms() - last_move < INERTIA_RELEASE_MS {
+            spin_with_inertia(points, state, axis, speed);
+        }
+    }
     is_rotating.set(false);
 }
 
+/// Points a keyboard action applies to: the selected points and the members of
+/// their groups
+fn affected_points(s: &State) -> Vec<usize> {
+    let mut affected_points = Vec::new();
+    for &selected_id in s.selected().iter() {
+        let group_members = s.get_group_members(selected_id);
+        for member in group_members {
+            if !affected_points.contains(&member) {
+                affected_points.push(member);
+            }
+        }
+    }
+    affected_points
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn handle_key_event(
     event: Event<KeyboardData>,
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+    keymap: Signal<Keymap>,
+    mut show_shortcuts: Signal<bool>,
+    mut show_palette: Signal<bool>,
+) {
+    event.prevent_default();
+    let binding = KeyBinding::from_event(&event.key(), event.modifiers());
+    let action = binding
+        .as_ref()
+        .and_then(|binding| keymap.read().action(binding))
+        // A character switching tools would cut into the name being typed, so it
+        // only does so while nothing is selected
+        .filter(|action| {
+            action.tool().is_none()
+                || state.read().selected().is_empty()
+                || !binding.as_ref().is_some_and(KeyBinding::types)
+        });
+    match action {
+        Some(KeyAction::ShowShortcuts) => show_shortcuts.set(true),
+        Some(KeyAction::OpenPalette) => show_palette.set(true),
+        Some(action) => run_key_action(action, points, arcs, great_circles, small_circles, state),
+        None => type_name(
+            event.key(),
+            event.modifiers().shift(),
+            points,
+            great_circles,
+            small_circles,
+            state,
+        ),
+    }
+}
+
+/// Runs a keyboard action on the current selection. Actions that open dialogs
+/// are left to the caller.
+pub fn run_key_action(
+    action: KeyAction,
     mut points: Signal<Vec<Point>>,
     mut arcs: Signal<Vec<(usize, usize)>>,
     mut great_circles: Signal<Vec<GreatCircle>>,
     mut small_circles: Signal<Vec<SmallCircle>>,
     mut state: Signal<State>,
 ) {
-    event.prevent_default();
+    // View animations need the state signal itself, so handle them before borrowing it
+    match action {
+        KeyAction::LookAtSelected => return look_at_selected(points, state),
+        KeyAction::LookAtPole => return look_at_great_circle_pole(points, state),
+        KeyAction::ResetView => return reset_view(points, state),
+        _ => {}
+    }
+
     let q = state.read().quaternion;
     let mut s = state.write();
 
+    if let Some(tool) = action.tool() {
+        s.tool = tool;
+        s.clear_selection();
+        return;
+    }
+
     // Handle group operations first
-    match event.key() {
-        Key::Character(ref c)
-            if (c.as_str() == "g" || c.as_str() == "G") && event.modifiers().ctrl() =>
-        {
+    match action {
+        KeyAction::Group => {
             s.create_group_from_selected();
             return;
         }
-        Key::Character(ref c)
-            if (c.as_str() == "u" || c.as_str() == "U") && event.modifiers().ctrl() =>
-        {
+        KeyAction::Ungroup => {
             s.ungroup_selected();
             return;
         }
-        Key::Character(ref c)
-            if (c.as_str() == "h" || c.as_str() == "H") && event.modifiers().ctrl() =>
-        {
+        KeyAction::ToggleHidden => {
             for &selected_id in s.selected().iter() {
                 let group_members = s.get_group_members(selected_id);
                 for member in group_members {
@@ -259,74 +424,32 @@ pub fn handle_key_event(
             }
             return;
         }
-        Key::Character(ref c)
-            if (c.as_str() == "a" || c.as_str() == "A") && event.modifiers().ctrl() =>
-        {
-            // Rotate around X axis
-            let rotation_step = 2.0; // smaller step for smoother rotation (degrees)
-            let rotation = Quaternion::from_axis_angle(
-                [1.0, 0.0, 0.0],
-                rotation_step * std::f64::consts::PI / 180.0,
-            );
-            let new_rotation = rotation.multiply(s.quaternion);
-            s.quaternion = new_rotation;
-            s.rotation = new_rotation.to_euler_deg();
-            for point in points.write().iter_mut() {
-                point.rotate(new_rotation);
-            }
-            return;
-        }
-        Key::Character(ref c)
-            if (c.as_str() == "s" || c.as_str() == "S") && event.modifiers().ctrl() =>
-        {
-            // Rotate around Y axis
-            let rotation_step = 2.0; // smaller step for smoother rotation (degrees)
-            let rotation = Quaternion::from_axis_angle(
-                [0.0, 1.0, 0.0],
-                rotation_step * std::f64::consts::PI / 180.0,
-            );
-            let new_rotation = rotation.multiply(s.quaternion);
-            s.quaternion = new_rotation;
-            s.rotation = new_rotation.to_euler_deg();
-            for point in points.write().iter_mut() {
-                point.rotate(new_rotation);
-            }
-            return;
-        }
-        Key::Character(ref c)
-            if (c.as_str() == "d" || c.as_str() == "D") && event.modifiers().ctrl() =>
-        {
-            // Rotate around Z axis
-            let rotation_step = 2.0; // smaller step for smoother rotation (degrees)
-            let rotation = Quaternion::from_axis_angle(
-                [0.0, 0.0, 1.0],
-                rotation_step * std::f64::consts::PI / 180.0,
-            );
-            let new_rotation = rotation.multiply(s.quaternion);
-            s.quaternion = new_rotation;
-            s.rotation = new_rotation.to_euler_deg();
-            for point in points.write().iter_mut() {
-                point.rotate(new_rotation);
-            }
-            return;
-        }
         _ => {}
     }
 
-    // Get all points that should be affected (including group members)
-    let mut affected_points = Vec::new();
-    for &selected_id in s.selected().iter() {
-        let group_members = s.get_group_members(selected_id);
-        for member in group_members {
-            if !affected_points.contains(&member) {
-                affected_points.push(member);
-            }
+    let axis = match action {
+        KeyAction::RotateX => Some([1.0, 0.0, 0.0]),
+        KeyAction::RotateY => Some([0.0, 1.0, 0.0]),
+        KeyAction::RotateZ => Some([0.0, 0.0, 1.0]),
+        _ => None,
+    };
+    if let Some(axis) = axis {
+        let rotation_step = 2.0; // smaller step for smoother rotation (degrees)
+        let rotation =
+            Quaternion::from_axis_angle(axis, rotation_step * std::f64::consts::PI / 180.0);
+        s.cancel_view_animation();
+        let new_rotation = rotation.multiply(s.quaternion);
+        s.quaternion = new_rotation;
+        s.rotation = new_rotation.to_euler_deg();
+        for point in points.write().iter_mut() {
+            point.rotate(new_rotation);
         }
+        return;
     }
 
-    for i in affected_points.iter().rev().copied().collect::<Vec<_>>() {
-        match event.key() {
-            Key::Delete => {
+    for i in affected_points(&s).into_iter().rev() {
+        match action {
+            KeyAction::DeleteSelected => {
                 if !points()[i].removable {
                     continue;
                 }
@@ -378,49 +501,39 @@ pub fn handle_key_event(
                             }
                         }
 
+                        fix_construction_indices(
+                            &mut points.write(),
+                            &mut small_circles.write(),
+                            member_idx,
+                            last_idx,
+                        );
+                        fix_constraint_indices(&mut s.constraints, member_idx, last_idx);
+                        fix_trace_indices(&mut s.traces, member_idx, last_idx);
+                        s.grid.fix_reference_index(member_idx, last_idx);
                         s.update_group_indices(member_idx);
                     }
                 }
                 s.clear_selection();
                 break;
             }
-            Key::Escape => {
+            KeyAction::ClearSelection => {
                 s.clear_selection();
                 break;
             }
-            Key::Character(ref c) if c.as_str() == "." => {
+            KeyAction::GreatCircleAroundPole => {
                 if great_circles().iter().all(|x| x.pole != i) {
                     great_circles.write().push(GreatCircle::new(i));
                 } else {
-                    great_circles.write().retain(|x| x.pole != i);
+                    remove_great_circle(&mut great_circles.write(), &mut s.grid, i);
                 }
             }
-            Key::Character(ref c) if c.as_str() == ">" && event.modifiers().shift() => {
+            KeyAction::GreatCircleThroughPoints => {
                 let selected = s.selected();
                 if selected.len() == 2 {
-                    let p1 = points()[selected[0]].absolute;
-                    let p2 = points()[selected[1]].absolute;
-                    let cross = [
-                        p1[1] * p2[2] - p1[2] * p2[1],
-                        p1[2] * p2[0] - p1[0] * p2[2],
-                        p1[0] * p2[1] - p1[1] * p2[0],
-                    ];
-                    let mag2 = cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2];
-                    let normal = if mag2 < 1e-10 {
-                        let perp = if p1[0].abs() < p1[1].abs() && p1[0].abs() < p1[2].abs() {
-                            [0.0, -p1[2], p1[1]]
-                        } else if p1[1].abs() < p1[2].abs() {
-                            [p1[2], 0.0, -p1[0]]
-                        } else {
-                            [-p1[1], p1[0], 0.0]
-                        };
-                        let mag =
-                            (perp[0] * perp[0] + perp[1] * perp[1] + perp[2] * perp[2]).sqrt();
-                        [perp[0] / mag, perp[1] / mag, perp[2] / mag]
-                    } else {
-                        let mag = mag2.sqrt();
-                        [cross[0] / mag, cross[1] / mag, cross[2] / mag]
-                    };
+                    let normal = great_circle_pole(
+                        points()[selected[0]].absolute,
+                        points()[selected[1]].absolute,
+                    );
                     // Remove existing parallel/antiparallel pole
                     if let Some(idx) = points().iter().enumerate().find_map(|(idx, point)| {
                         if great_circles.read().iter().any(|gc| gc.pole == idx) {
@@ -436,10 +549,12 @@ pub fn handle_key_event(
                             None
                         }
                     }) {
-                        great_circles.write().retain(|gc| gc.pole != idx);
+                        remove_great_circle(&mut great_circles.write(), &mut s.grid, idx);
                         return;
                     }
-                    let new_point = Point::from_vec3_absolute(points().len(), normal, q);
+                    let mut new_point = Point::from_vec3_absolute(points().len(), normal, q);
+                    new_point.construction =
+                        Some(Construction::GreatCirclePole(selected[0], selected[1]));
                     points.write().push(new_point);
                     great_circles
                         .write()
@@ -447,34 +562,17 @@ pub fn handle_key_event(
                 }
                 break;
             }
-            Key::Character(ref c) if c.as_str() == "," => {
+            KeyAction::SmallCircleThroughPoints => {
                 let selected = s.selected();
                 if selected.len() == 3 {
                     let p1 = points()[selected[0]].absolute;
-                    let p2 = points()[selected[1]].absolute;
-                    let p3 = points()[selected[2]].absolute;
-                    let v1 = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
-                    let v2 = [p3[0] - p1[0], p3[1] - p1[1], p3[2] - p1[2]];
-                    let normal = [
-                        v1[1] * v2[2] - v1[2] * v2[1],
-                        v1[2] * v2[0] - v1[0] * v2[2],
-                        v1[0] * v2[1] - v1[1] * v2[0],
-                    ];
-                    let mag2 =
-                        normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2];
-                    if mag2 < 1e-10 {
+                    let Some(n) = small_circle_pole(
+                        p1,
+                        points()[selected[1]].absolute,
+                        points()[selected[2]].absolute,
+                    ) else {
                         return;
-                    }
-                    let mag = mag2.sqrt();
-                    let mut n = [normal[0] / mag, normal[1] / mag, normal[2] / mag];
-                    let dots = [
-                        n[0] * p1[0] + n[1] * p1[1] + n[2] * p1[2],
-                        n[0] * p2[0] + n[1] * p2[1] + n[2] * p2[2],
-                        n[0] * p3[0] + n[1] * p3[1] + n[2] * p3[2],
-                    ];
-                    if dots.iter().filter(|&&d| d < 0.0).count() >= 2 {
-                        n = [-n[0], -n[1], -n[2]];
-                    }
+                    };
                     if let Some(idx) = points().iter().enumerate().find_map(|(idx, point)| {
                         if small_circles.read().iter().any(|sc| sc.pole == idx) {
                             let dot = n[0] * point.absolute[0]
@@ -494,15 +592,22 @@ pub fn handle_key_event(
                     }
                     let plane_distance = p1[0] * n[0] + p1[1] * n[1] + p1[2] * n[2];
                     let new_pole_idx = points().len();
-                    let new_pole = Point::from_vec3_absolute(new_pole_idx, n, q);
+                    let mut new_pole = Point::from_vec3_absolute(new_pole_idx, n, q);
+                    new_pole.construction = Some(Construction::SmallCirclePole(
+                        selected[0],
+                        selected[1],
+                        selected[2],
+                    ));
                     points.write().push(new_pole);
-                    small_circles
-                        .write()
-                        .push(SmallCircle::new(new_pole_idx, plane_distance));
+                    small_circles.write().push(SmallCircle::through(
+                        new_pole_idx,
+                        plane_distance,
+                        selected[0],
+                    ));
                 }
                 break;
             }
-            Key::Character(ref c) if c.as_str() == "<" => {
+            KeyAction::SmallCircleAroundPole => {
                 let selected = s.selected();
                 if selected.len() == 2 {
                     let pole_idx = selected[0];
@@ -514,23 +619,42 @@ pub fn handle_key_event(
                         let point = points()[point_idx].absolute;
                         let plane_distance =
                             pole[0] * point[0] + pole[1] * point[1] + pole[2] * point[2];
-                        small_circles
-                            .write()
-                            .push(SmallCircle::new(pole_idx, plane_distance));
+                        small_circles.write().push(SmallCircle::through(
+                            pole_idx,
+                            plane_distance,
+                            point_idx,
+                        ));
                     }
                 }
                 break;
             }
-            Key::Character(ref c) if c.as_str() == "/" => {
+            KeyAction::Antipode => {
                 let new = points()[i].new_inverted(points().len());
                 points.write().push(new);
             }
+            _ => {}
+        }
+    }
+}
+
+/// Edits the names of the selected points as keys are typed; with Shift, the
+/// names of the circles they are poles of
+fn type_name(
+    key: Key,
+    shift: bool,
+    mut points: Signal<Vec<Point>>,
+    mut great_circles: Signal<Vec<GreatCircle>>,
+    mut small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+) {
+    for i in affected_points(&state.read()).into_iter().rev() {
+        match &key {
             Key::Character(c) => {
                 if let Some(gc) = great_circles.write().iter_mut().find(|x| x.pole == i) {
-                    if event.modifiers().shift() {
+                    if shift {
                         gc.name.push_str(&{
                             let up = c.to_uppercase();
-                            if up == c {
+                            if up == *c {
                                 c.to_lowercase()
                             } else {
                                 c.to_uppercase()
@@ -539,10 +663,10 @@ pub fn handle_key_event(
                         continue;
                     }
                 } else if let Some(sc) = small_circles.write().iter_mut().find(|x| x.pole == i) {
-                    if event.modifiers().shift() {
+                    if shift {
                         sc.name.push_str(&{
                             let up = c.to_uppercase();
-                            if up == c {
+                            if up == *c {
                                 c.to_lowercase()
                             } else {
                                 c.to_uppercase()
@@ -551,16 +675,16 @@ pub fn handle_key_event(
                         continue;
                     }
                 }
-                points.write()[i].name.push_str(&c);
+                points.write()[i].name.push_str(c);
             }
             Key::Backspace => {
                 if let Some(gc) = great_circles.write().iter_mut().find(|x| x.pole == i) {
-                    if event.modifiers().shift() {
+                    if shift {
                         gc.name.pop();
                         continue;
                     }
                 } else if let Some(sc) = small_circles.write().iter_mut().find(|x| x.pole == i) {
-                    if event.modifiers().shift() {
+                    if shift {
                         sc.name.pop();
                         continue;
                     }
@@ -0,0 +1,244 @@
+use super::scene::*;
+use ab_glyph::{Font, FontRef, OutlineCurve};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, StrokeDash, Transform};
+
+const FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+/// Outline width of synthetic bold text, relative to the font size
+const BOLD_STROKE: f64 = 0.04;
+
+/// Settings of a PNG export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PngOptions {
+    /// Width of the image in pixels; the height follows the scene's aspect ratio
+    pub width: u32,
+    /// Resolution recorded in the file, which sets the printed size
+    pub dpi: f64,
+    pub transparent: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            width: 1200,
+            dpi: 150.0,
+            transparent: false,
+        }
+    }
+}
+
+fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(
+        color.r,
+        color.g,
+        color.b,
+        (color.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+    );
+    paint.anti_alias = true;
+    paint
+}
+
+fn skia_stroke(stroke: &Stroke) -> tiny_skia::Stroke {
+    tiny_skia::Stroke {
+        width: stroke.width as f32,
+        line_cap: tiny_skia::LineCap::Round,
+        line_join: tiny_skia::LineJoin::Round,
+        dash: stroke
+            .dash
+            .and_then(|dash| StrokeDash::new(vec![dash as f32, dash as f32], 0.0)),
+        ..Default::default()
+    }
+}
+
+fn draw(
+    pixmap: &mut Pixmap,
+    path: &tiny_skia::Path,
+    stroke: Option<Stroke>,
+    fill: Option<Color>,
+    transform: Transform,
+) {
+    if let Some(fill) = fill {
+        pixmap.fill_path(path, &paint(fill), FillRule::Winding, transform, None);
+    }
+    if let Some(stroke) = stroke {
+        pixmap.stroke_path(
+            path,
+            &paint(stroke.color),
+            &skia_stroke(&stroke),
+            transform,
+            None,
+        );
+    }
+}
+
+/// Glyph outlines of `text` laid out on a baseline through the origin
+fn text_path(font: &FontRef, text: &str, size: f64, anchor: Anchor) -> Option<tiny_skia::Path> {
+    let scale = size as f32 / font.units_per_em()?;
+    let glyphs = text.chars().map(|c| font.glyph_id(c)).collect::<Vec<_>>();
+    let advances = glyphs
+        .iter()
+        .enumerate()
+        .map(|(i, &id)| {
+            let kern = glyphs
+                .get(i + 1)
+                .map_or(0.0, |&next| font.kern_unscaled(id, next));
+            (font.h_advance_unscaled(id) + kern) * scale
+        })
+        .collect::<Vec<_>>();
+    let width: f32 = advances.iter().sum();
+    let mut pen = match anchor {
+        Anchor::Start => 0.0,
+        Anchor::Middle => -width / 2.0,
+    };
+
+    let mut builder = PathBuilder::new();
+    for (&id, advance) in glyphs.iter().zip(advances) {
+        if let Some(outline) = font.outline(id) {
+            // Font units have y pointing up
+            let map = |p: ab_glyph::Point| (pen + p.x * scale, -p.y * scale);
+            let mut last = None;
+            for curve in &outline.curves {
+                let (start, end) = match curve {
+                    OutlineCurve::Line(a, b) => (*a, *b),
+                    OutlineCurve::Quad(a, _, b) => (*a, *b),
+                    OutlineCurve::Cubic(a, _, _, b) => (*a, *b),
+                };
+                if last != Some(start) {
+                    if last.is_some() {
+                        builder.close();
+                    }
+                    let (x, y) = map(start);
+                    builder.move_to(x, y);
+                }
+                match curve {
+                    OutlineCurve::Line(_, b) => {
+                        let (x, y) = map(*b);
+                        builder.line_to(x, y);
+                    }
+                    OutlineCurve::Quad(_, c, b) => {
+                        let ((cx, cy), (x, y)) = (map(*c), map(*b));
+                        builder.quad_to(cx, cy, x, y);
+                    }
+                    OutlineCurve::Cubic(_, c1, c2, b) => {
+                        let ((c1x, c1y), (c2x, c2y), (x, y)) = (map(*c1), map(*c2), map(*b));
+                        builder.cubic_to(c1x, c1y, c2x, c2y, x, y);
+                    }
+                }
+                last = Some(end);
+            }
+            if last.is_some() {
+                builder.close();
+            }
+        }
+        pen += advance;
+    }
+    builder.finish()
+}
+
+/// Rasterizes the scene into a PNG file
+pub fn scene_to_png(scene: &Scene, options: &PngOptions) -> Result<Vec<u8>, String> {
+    let scale = options.width as f64 / scene.width;
+    let height = (scene.height * scale).round().max(1.0) as u32;
+    let mut pixmap = Pixmap::new(options.width.max(1), height)
+        .ok_or_else(|| format!("Cannot create a {}×{} image", options.width, height))?;
+    let transform = Transform::from_scale(scale as f32, scale as f32);
+    let font = FontRef::try_from_slice(FONT).map_err(|e| e.to_string())?;
+
+    if !options.transparent {
+        let Color { r, g, b, a } = scene.background;
+        pixmap.fill(tiny_skia::Color::from_rgba8(
+            r,
+            g,
+            b,
+            (a * 255.0).round() as u8,
+        ));
+    }
+
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Path {
+                runs,
+                closed,
+                stroke,
+                fill,
+                ..
+            } => {
+                let mut builder = PathBuilder::new();
+                for run in runs {
+                    for (i, &[x, y]) in run.iter().enumerate() {
+                        if i == 0 {
+                            builder.move_to(x as f32, y as f32);
+                        } else {
+                            builder.line_to(x as f32, y as f32);
+                        }
+                    }
+                    if *closed {
+                        builder.close();
+                    }
+                }
+                if let Some(path) = builder.finish() {
+                    draw(&mut pixmap, &path, *stroke, *fill, transform);
+                }
+            }
+            Shape::Circle {
+                center: [x, y],
+                radius,
+                stroke,
+                fill,
+            } => {
+                if let Some(path) = PathBuilder::from_circle(*x as f32, *y as f32, *radius as f32) {
+                    draw(&mut pixmap, &path, *stroke, *fill, transform);
+                }
+            }
+            Shape::Text {
+                position: [x, y],
+                text,
+                size,
+                bold,
+                anchor,
+                color,
+            } => {
+                let Some(path) = text_path(&font, text, *size, *anchor) else {
+                    continue;
+                };
+                // Fonts only come in one weight, so bold text is thickened with an outline
+                let stroke = bold.then_some(Stroke {
+                    color: *color,
+                    width: size * BOLD_STROKE,
+                    dash: None,
+                });
+                let transform = transform.pre_translate(*x as f32, *y as f32);
+                draw(&mut pixmap, &path, stroke, Some(*color), transform);
+            }
+        }
+    }
+
+    encode_png(&pixmap, options.dpi)
+}
+
+fn encode_png(pixmap: &Pixmap, dpi: f64) -> Result<Vec<u8>, String> {
+    // PNG stores straight rather than premultiplied alpha
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let c = pixel.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect::<Vec<_>>();
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, pixmap.width(), pixmap.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let pixels_per_meter = (dpi / 0.0254).round() as u32;
+    encoder.set_pixel_dims(Some(png::PixelDimensions {
+        xppu: pixels_per_meter,
+        yppu: pixels_per_meter,
+        unit: png::Unit::Meter,
+    }));
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&data).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
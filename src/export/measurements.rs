@@ -0,0 +1,135 @@
+use crate::*;
+
+/// Titled block of label/value rows, as shown in the info boxes of the left panel
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasurementSection {
+    pub title: String,
+    pub rows: Vec<(String, String)>,
+}
+
+fn point_label(points: &[Point], id: usize) -> String {
+    match points.get(id) {
+        Some(point) if !point.name.is_empty() => format!("{} ({id})", point.name),
+        _ => id.to_string(),
+    }
+}
+
+/// Names shown in the info boxes, which would otherwise be blank
+fn circle_name(name: &str) -> String {
+    if name.is_empty() {
+        "[unnamed]".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Measurements of the current selection and the constraints, for the PDF export
+pub fn measurement_sections(
+    points: &[Point],
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+    state: &State,
+) -> Vec<MeasurementSection> {
+    let mut sections = selection_sections(points, great_circles, small_circles, state);
+    if !state.constraints.is_empty() {
+        sections.push(MeasurementSection {
+            title: "Constraints".to_string(),
+            rows: state
+                .constraints
+                .iter()
+                .enumerate()
+                .map(|(i, constraint)| (format!("{}", i + 1), constraint.describe(points)))
+                .collect(),
+        });
+    }
+    sections
+}
+
+/// Measurements of the current selection, as listed in the info boxes of the
+/// left panel
+pub fn selection_sections(
+    points: &[Point],
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+    state: &State,
+) -> Vec<MeasurementSection> {
+    let mut sections = Vec::new();
+    let selected = state.selected();
+    let row = |label: &str, value: String| (label.to_string(), value);
+
+    if let Some(group) = selected
+        .first()
+        .and_then(|&id| state.find_group_containing(id))
+        .map(|index| &state.groups[index])
+        .filter(|group| selected.len() > 1 && group.iter().all(|id| selected.contains(id)))
+    {
+        sections.push(MeasurementSection {
+            title: "Group".to_string(),
+            rows: vec![
+                row("Size", format!("{} points", group.len())),
+                row(
+                    "Members",
+                    group
+                        .iter()
+                        .map(|&id| point_label(points, id))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+            ],
+        });
+    }
+
+    if let &[pole] = selected {
+        if let Some(gc) = great_circles.iter().find(|gc| gc.pole == pole) {
+            sections.push(MeasurementSection {
+                title: "Great Circle".to_string(),
+                rows: vec![
+                    row("Pole", point_label(points, pole)),
+                    row("Name", circle_name(&gc.name)),
+                ],
+            });
+        }
+        if let Some(sc) = small_circles.iter().find(|sc| sc.pole == pole) {
+            let mut rows = vec![
+                row("Pole", point_label(points, pole)),
+                row("Name", circle_name(&sc.name)),
+                row("Plane distance", format!("{:.4}", sc.plane_distance)),
+                row(
+                    "Radius",
+                    format!("{:.2}", (1.0 - sc.plane_distance.powi(2)).sqrt()),
+                ),
+            ];
+            if let Some(through) = sc.through {
+                rows.push(row("Through point", point_label(points, through)));
+            }
+            sections.push(MeasurementSection {
+                title: "Small Circle".to_string(),
+                rows,
+            });
+        }
+    }
+
+    if let &[a, b, c] = selected {
+        let [side_a, side_b, side_c, angle_a, angle_b, angle_c, excess] =
+            spherical_triangle(points[a].absolute, points[b].absolute, points[c].absolute);
+        sections.push(MeasurementSection {
+            title: format!(
+                "Spherical Triangle {}, {}, {}",
+                point_label(points, a),
+                point_label(points, b),
+                point_label(points, c)
+            ),
+            rows: vec![
+                row("Side a", format!("{side_a:.4}°")),
+                row("Side b", format!("{side_b:.4}°")),
+                row("Side c", format!("{side_c:.4}°")),
+                row("Angle A", format!("{angle_a:.4}°")),
+                row("Angle B", format!("{angle_b:.4}°")),
+                row("Angle C", format!("{angle_c:.4}°")),
+                row("Spherical excess", format!("{excess:.4}°")),
+            ],
+        });
+    }
+
+    sections
+}
@@ -0,0 +1,13 @@
+mod bitmap;
+mod measurements;
+mod pdf;
+mod scene;
+mod svg;
+mod tikz;
+
+pub use bitmap::*;
+pub use measurements::*;
+pub use pdf::*;
+pub use scene::*;
+pub use svg::*;
+pub use tikz::*;
@@ -0,0 +1,405 @@
+use super::measurements::*;
+use super::scene::*;
+use std::fmt::Write;
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+/// Bézier handle length for approximating a quarter circle
+const CIRCLE_KAPPA: f64 = 0.552_284_75;
+
+/// Advance widths of the printable ASCII characters in Helvetica, in 1/1000 em
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 5] = [
+        PageSize::A4,
+        PageSize::A3,
+        PageSize::A5,
+        PageSize::Letter,
+        PageSize::Legal,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PageSize::A4 => "A4",
+            PageSize::A3 => "A3",
+            PageSize::A5 => "A5",
+            PageSize::Letter => "Letter",
+            PageSize::Legal => "Legal",
+        }
+    }
+
+    /// Width and height in PDF points, portrait
+    fn dimensions(self) -> (f64, f64) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::A3 => (841.89, 1190.55),
+            PageSize::A5 => (419.53, 595.28),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Legal => (612.0, 1008.0),
+        }
+    }
+}
+
+/// Settings of a PDF export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfOptions {
+    pub page: PageSize,
+    /// Blank border on every side of the page, in millimeters
+    pub margin_mm: f64,
+    /// Adds a page with the measurements of the current selection
+    pub measurements: bool,
+    /// Draws on white paper instead of the screen's dark background
+    pub printable: bool,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            page: PageSize::A4,
+            margin_mm: 15.0,
+            measurements: false,
+            printable: true,
+        }
+    }
+}
+
+/// Encodes text for the standard fonts, which use WinAnsiEncoding; characters
+/// outside Latin-1 are replaced by question marks
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => bytes.push(c as u32 as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes.push(b')');
+    bytes
+}
+
+fn text_width(text: &str, size: f64) -> f64 {
+    let units: f64 = text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - 32] as f64,
+            '°' => 400.0,
+            _ => 556.0,
+        })
+        .sum();
+    units * size / 1000.0
+}
+
+/// Builds a page's content stream and records the opacities it uses
+struct Content {
+    bytes: Vec<u8>,
+    opacities: Vec<f64>,
+}
+
+impl Content {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            opacities: Vec::new(),
+        }
+    }
+
+    fn op(&mut self, op: &str) {
+        self.bytes.extend_from_slice(op.as_bytes());
+        self.bytes.push(b'\n');
+    }
+
+    /// Selects the graphics state for an opacity, rounded to percent
+    fn opacity(&mut self, alpha: f64) {
+        let percent = (alpha.clamp(0.0, 1.0) * 100.0).round();
+        let index = match self.opacities.iter().position(|&a| a == percent) {
+            Some(index) => index,
+            None => {
+                self.opacities.push(percent);
+                self.opacities.len() - 1
+            }
+        };
+        self.op(&format!("/GS{index} gs"));
+    }
+
+    fn color(&mut self, color: Color, op: &str) {
+        self.op(&format!(
+            "{:.3} {:.3} {:.3} {op}",
+            color.r as f64 / 255.0,
+            color.g as f64 / 255.0,
+            color.b as f64 / 255.0
+        ));
+    }
+
+    /// Sets colors and line style; must come before the path is constructed
+    fn style(&mut self, stroke: Option<Stroke>, fill: Option<Color>) {
+        // Opacity is shared by stroke and fill, which scenes never mix
+        if let Some(color) = fill.or(stroke.map(|s| s.color)) {
+            self.opacity(color.a);
+        }
+        if let Some(fill) = fill {
+            self.color(fill, "rg");
+        }
+        if let Some(stroke) = stroke {
+            self.color(stroke.color, "RG");
+            self.op(&format!("{:.2} w", stroke.width));
+            match stroke.dash {
+                Some(dash) => self.op(&format!("[{dash:.2} {dash:.2}] 0 d")),
+                None => self.op("[] 0 d"),
+            }
+        }
+    }
+
+    /// Paints the path constructed since `style`
+    fn paint(&mut self, stroke: Option<Stroke>, fill: Option<Color>) {
+        self.op(match (stroke.is_some(), fill.is_some()) {
+            (true, true) => "B",
+            (true, false) => "S",
+            (false, true) => "f",
+            (false, false) => "n",
+        });
+    }
+
+    fn text(&mut self, text: &str, x: f64, y: f64, size: f64, bold: bool, flipped: bool) {
+        let font = if bold { "F2" } else { "F1" };
+        let d = if flipped { -1 } else { 1 };
+        self.op(&format!(
+            "BT /{font} {size:.2} Tf 1 0 0 {d} {x:.2} {y:.2} Tm"
+        ));
+        self.bytes.extend(pdf_string(text));
+        self.op(" Tj ET");
+    }
+
+    fn scene(&mut self, scene: &Scene) {
+        self.op("1 J 1 j");
+        self.opacity(scene.background.a);
+        self.color(scene.background, "rg");
+        self.op(&format!("0 0 {:.2} {:.2} re f", scene.width, scene.height));
+
+        for shape in &scene.shapes {
+            match shape {
+                Shape::Path {
+                    runs,
+                    closed,
+                    stroke,
+                    fill,
+                    ..
+                } => {
+                    self.style(*stroke, *fill);
+                    let mut path = String::new();
+                    for run in runs {
+                        for (i, [x, y]) in run.iter().enumerate() {
+                            let op = if i == 0 { "m" } else { "l" };
+                            let _ = writeln!(path, "{x:.2} {y:.2} {op}");
+                        }
+                        if *closed {
+                            path.push_str("h\n");
+                        }
+                    }
+                    self.bytes.extend_from_slice(path.as_bytes());
+                    self.paint(*stroke, *fill);
+                }
+                Shape::Circle {
+                    center: [cx, cy],
+                    radius: r,
+                    stroke,
+                    fill,
+                } => {
+                    self.style(*stroke, *fill);
+                    let k = r * CIRCLE_KAPPA;
+                    self.op(&format!("{:.2} {cy:.2} m", cx + r));
+                    for [c1x, c1y, c2x, c2y, x, y] in [
+                        [cx + r, cy + k, cx + k, cy + r, *cx, cy + r],
+                        [cx - k, cy + r, cx - r, cy + k, cx - r, *cy],
+                        [cx - r, cy - k, cx - k, cy - r, *cx, cy - r],
+                        [cx + k, cy - r, cx + r, cy - k, cx + r, *cy],
+                    ] {
+                        self.op(&format!(
+                            "{c1x:.2} {c1y:.2} {c2x:.2} {c2y:.2} {x:.2} {y:.2} c"
+                        ));
+                    }
+                    self.op("h");
+                    self.paint(*stroke, *fill);
+                }
+                Shape::Text {
+                    position: [x, y],
+                    text,
+                    size,
+                    bold,
+                    anchor,
+                    color,
+                } => {
+                    let x = match anchor {
+                        Anchor::Start => *x,
+                        Anchor::Middle => x - text_width(text, *size) / 2.0,
+                    };
+                    self.opacity(color.a);
+                    self.color(*color, "rg");
+                    self.text(text, x, *y, *size, *bold, true);
+                }
+            }
+        }
+    }
+}
+
+/// Lays out the measurement table over as many pages as it needs
+fn measurement_pages(
+    sections: &[MeasurementSection],
+    width: f64,
+    height: f64,
+    margin: f64,
+) -> Vec<Content> {
+    const LINE: f64 = 14.0;
+    let mut pages = Vec::new();
+    let mut page = Content::new();
+    let mut y = height - margin - 16.0;
+    page.text("Measurements", margin, y, 16.0, true, false);
+    y -= 2.0 * LINE;
+
+    let mut lines = Vec::new();
+    if sections.is_empty() {
+        lines.push((
+            "Nothing is selected. Select points to list their measurements.".to_string(),
+            String::new(),
+            false,
+        ));
+    }
+    for section in sections {
+        lines.push((section.title.clone(), String::new(), true));
+        for (label, value) in &section.rows {
+            lines.push((label.clone(), value.clone(), false));
+        }
+        lines.push((String::new(), String::new(), false));
+    }
+
+    let value_x = margin + (width - 2.0 * margin).min(150.0);
+    for (label, value, heading) in lines {
+        if y < margin {
+            pages.push(std::mem::replace(&mut page, Content::new()));
+            y = height - margin - LINE;
+        }
+        let size = if heading { 12.0 } else { 10.0 };
+        page.text(&label, margin, y, size, heading, false);
+        if !value.is_empty() {
+            page.text(&value, value_x, y, size, false, false);
+        }
+        y -= LINE;
+    }
+    pages.push(page);
+    pages
+}
+
+/// Writes the scene, and optionally the measurements, as a PDF document
+pub fn scene_to_pdf(
+    scene: &Scene,
+    measurements: &[MeasurementSection],
+    options: &PdfOptions,
+) -> Vec<u8> {
+    let (width, height) = options.page.dimensions();
+    let margin = (options.margin_mm * POINTS_PER_MM).clamp(0.0, width.min(height) / 2.0 - 36.0);
+    let scene = if options.printable {
+        scene.printable()
+    } else {
+        scene.clone()
+    };
+
+    // Fit the picture to the top of the page, with y pointing down as in the scene
+    let (box_width, box_height) = (width - 2.0 * margin, height - 2.0 * margin);
+    let scale = (box_width / scene.width).min(box_height / scene.height);
+    let left = margin + (box_width - scene.width * scale) / 2.0;
+    let mut picture = Content::new();
+    picture.op(&format!(
+        "q {scale:.4} 0 0 {:.4} {left:.2} {:.2} cm",
+        -scale,
+        height - margin
+    ));
+    picture.scene(&scene);
+    picture.op("Q");
+
+    let mut pages = vec![picture];
+    if options.measurements {
+        pages.extend(measurement_pages(measurements, width, height, margin));
+    }
+
+    // Objects 1-4 are the catalog, page tree and fonts; each page adds itself and its content
+    let page_ids = (0..pages.len()).map(|i| 5 + 2 * i).collect::<Vec<_>>();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        let states = page
+            .opacities
+            .iter()
+            .enumerate()
+            .map(|(i, percent)| {
+                let alpha = percent / 100.0;
+                format!("/GS{i} << /Type /ExtGState /CA {alpha:.2} /ca {alpha:.2} >>")
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width:.2} {height:.2}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> /ExtGState << {states} >> >> \
+                 /Contents {} 0 R >>",
+                id + 1
+            )
+            .into_bytes(),
+        );
+        let mut stream = format!("<< /Length {} >>\nstream\n", page.bytes.len()).into_bytes();
+        stream.extend_from_slice(&page.bytes);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        table.push_str(&format!("{offset:010} 00000 n \n"));
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    pdf
+}
@@ -0,0 +1,663 @@
+use crate::*;
+
+/// Width and height of an exported drawing, in drawing units
+pub const SCENE_SIZE: f64 = 600.0;
+const SCENE_RADIUS: f64 = 250.0;
+const GRID_RESOLUTION: usize = 128;
+const ARC_RESOLUTION: usize = 64;
+const POINT_RADIUS_SMALL: f64 = 6.0;
+const POINT_RADIUS_LARGE: f64 = 12.0;
+/// Opacity of everything on the far side of the sphere
+const BACK_OPACITY: f64 = 0.4;
+
+const BACKGROUND: Color = Color::rgb(0x0f, 0x11, 0x16);
+const SPHERE_COLOR: Color = Color::rgb(0xbb, 0xbb, 0xbb);
+const ARC_COLOR: Color = Color::rgb(0xfb, 0xc0, 0x2d);
+const GREAT_CIRCLE_COLOR: Color = Color::rgb(0x00, 0xbc, 0xd4);
+const SMALL_CIRCLE_COLOR: Color = Color::rgb(0xe6, 0x4a, 0x19);
+const TRACE_COLOR: Color = Color::rgb(0xff, 0x4d, 0xd2);
+const POINT_COLOR: Color = Color::rgb(0xe5, 0x39, 0x35);
+const LABEL_COLOR: Color = Color::rgb(0xff, 0xff, 0xff);
+const SMALL_LABEL_COLOR: Color = Color::rgb(0xff, 0xb7, 0x4d);
+const CENTER_COLOR: Color = Color::rgb(0x00, 0x7a, 0xff);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Opacity between 0 and 1
+    pub a: f64,
+}
+
+impl Color {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub fn with_alpha(self, a: f64) -> Self {
+        Color { a, ..self }
+    }
+
+    /// Parses `#rgb` and `#rrggbb` colors, as produced by color inputs
+    pub fn parse(css: &str) -> Option<Self> {
+        let hex = css.trim().strip_prefix('#')?;
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        match hex.len() {
+            3 => {
+                let mut c = hex.chars().map(|c| channel(&c.to_string()).map(|v| v * 17));
+                Some(Color::rgb(c.next()??, c.next()??, c.next()??))
+            }
+            6 => Some(Color::rgb(
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub color: Color,
+    pub width: f64,
+    /// Length of the dashes and of the gaps between them
+    pub dash: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Start,
+    Middle,
+}
+
+/// Drawing primitive of an exported picture, in drawing units with y pointing down
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Path {
+        runs: Runs,
+        closed: bool,
+        stroke: Option<Stroke>,
+        fill: Option<Color>,
+        /// Whether the path lies on the far side of the sphere
+        back: bool,
+    },
+    Circle {
+        center: [f64; 2],
+        radius: f64,
+        stroke: Option<Stroke>,
+        fill: Option<Color>,
+    },
+    Text {
+        /// Start or middle of the baseline, depending on `anchor`
+        position: [f64; 2],
+        text: String,
+        size: f64,
+        bold: bool,
+        anchor: Anchor,
+        color: Color,
+    },
+}
+
+/// Format-independent description of the current view, shared by all exports
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub width: f64,
+    pub height: f64,
+    /// Color behind the sphere; exports may leave it out for a transparent picture
+    pub background: Color,
+    /// Shapes in painting order
+    pub shapes: Vec<Shape>,
+}
+
+impl Color {
+    /// Whether the color is a shade of grey rather than a hue
+    fn is_neutral(self) -> bool {
+        let channels = [self.r, self.g, self.b];
+        let (min, max) = (
+            channels.iter().min().copied().unwrap_or(0),
+            channels.iter().max().copied().unwrap_or(0),
+        );
+        max - min <= 32
+    }
+
+    /// Dark counterpart of a light grey, for drawing on white paper
+    fn for_paper(self) -> Self {
+        let luminance =
+            (0.299 * self.r as f64 + 0.587 * self.g as f64 + 0.114 * self.b as f64) / 255.0;
+        if self.is_neutral() && luminance > 0.5 {
+            Color {
+                r: 255 - self.r,
+                g: 255 - self.g,
+                b: 255 - self.b,
+                a: self.a,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+impl Scene {
+    /// Copy of the scene for printing on white paper. Light greys and white,
+    /// which stand out on the dark screen background, become the matching dark shades.
+    pub fn printable(&self) -> Scene {
+        let stroke = |stroke: Option<Stroke>| {
+            stroke.map(|s| Stroke {
+                color: s.color.for_paper(),
+                ..s
+            })
+        };
+        let shapes = self
+            .shapes
+            .iter()
+            .cloned()
+            .map(|shape| match shape {
+                Shape::Path {
+                    runs,
+                    closed,
+                    stroke: s,
+                    fill,
+                    back,
+                } => Shape::Path {
+                    runs,
+                    closed,
+                    stroke: stroke(s),
+                    fill: fill.map(Color::for_paper),
+                    back,
+                },
+                Shape::Circle {
+                    center,
+                    radius,
+                    stroke: s,
+                    fill,
+                } => Shape::Circle {
+                    center,
+                    radius,
+                    stroke: stroke(s),
+                    fill: fill.map(Color::for_paper),
+                },
+                Shape::Text {
+                    position,
+                    text,
+                    size,
+                    bold,
+                    anchor,
+                    color,
+                } => Shape::Text {
+                    position,
+                    text,
+                    size,
+                    bold,
+                    anchor,
+                    color: color.for_paper(),
+                },
+            })
+            .collect();
+        Scene {
+            background: Color::rgb(0xff, 0xff, 0xff),
+            shapes,
+            ..*self
+        }
+    }
+}
+
+// ===== Vector utility functions =====
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale_vec(a: [f64; 3], s: f64) -> [f64; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f64; 3]) -> [f64; 3] {
+    let len = (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    if len == 0.0 {
+        [0.0, 0.0, 0.0]
+    } else {
+        [a[0] / len, a[1] / len, a[2] / len]
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Two unit vectors spanning the plane perpendicular to `pole`
+fn circle_basis(pole: Vec3) -> (Vec3, Vec3) {
+    let v = if pole[2].abs() < 0.99 {
+        [pole[1], -pole[0], 0.0]
+    } else {
+        [1.0, 0.0, 0.0]
+    };
+    let v = normalize(cross(pole, v));
+    let w = normalize(cross(pole, v));
+    (v, w)
+}
+
+/// Maps the sphere into the drawing
+struct Frame {
+    radius: f64,
+    center: (f64, f64),
+    projection: Projection,
+}
+
+impl Frame {
+    fn project_point(&self, point: [f64; 3]) -> Option<[f64; 2]> {
+        let [x, y] = self.projection.project(point)?;
+        Some([
+            self.center.0 + x * self.radius,
+            self.center.1 + y * self.radius,
+        ])
+    }
+
+    fn to_drawing(&self, runs: Runs) -> Runs {
+        runs.into_iter()
+            .map(|run| {
+                run.into_iter()
+                    .map(|[x, y]| {
+                        [
+                            self.center.0 + x * self.radius,
+                            self.center.1 + y * self.radius,
+                        ]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Front and back paths of a polyline in the rotated frame, back first
+    fn polyline(&self, points: &[Vec3], stroke: Stroke, back_opacity: f64) -> Vec<Shape> {
+        let (front, back) = self.projection.project_polyline(points);
+        let mut shapes = Vec::new();
+        for (runs, back, opacity) in [(back, true, back_opacity), (front, false, stroke.color.a)] {
+            if runs.is_empty() {
+                continue;
+            }
+            shapes.push(Shape::Path {
+                runs: self.to_drawing(runs),
+                closed: false,
+                stroke: Some(Stroke {
+                    color: stroke.color.with_alpha(opacity),
+                    ..stroke
+                }),
+                fill: None,
+                back,
+            });
+        }
+        shapes
+    }
+}
+
+impl Scene {
+    /// Describes the current view of the diagram
+    pub fn build(
+        points: &[Point],
+        arcs: &[(usize, usize)],
+        great_circles: &[GreatCircle],
+        small_circles: &[SmallCircle],
+        state: &State,
+    ) -> Self {
+        let frame = Frame {
+            radius: SCENE_RADIUS / state.projection.extent(),
+            center: (SCENE_SIZE / 2.0, SCENE_SIZE / 2.0),
+            projection: state.projection,
+        };
+        let q = state.quaternion;
+        let mut shapes = Vec::new();
+
+        if state.show_grid {
+            let grid = &state.grid;
+            shapes.extend(grid_shapes(q.multiply(grid.frame(points)), grid, &frame));
+        }
+
+        // Central "earth"
+        if state.show_center {
+            shapes.push(Shape::Circle {
+                center: [frame.center.0, frame.center.1],
+                radius: frame.radius * 0.05,
+                stroke: None,
+                fill: Some(CENTER_COLOR.with_alpha(0.95)),
+            });
+        }
+
+        shapes.extend(great_circle_shapes(great_circles, points, q, &frame));
+        shapes.extend(small_circle_shapes(small_circles, points, q, &frame));
+        shapes.extend(arc_shapes(arcs, points, q, &frame));
+        shapes.extend(trace_shapes(&state.traces, q, &frame));
+
+        // Sphere boundary
+        let sphere_stroke = Some(Stroke {
+            color: SPHERE_COLOR,
+            width: 4.0,
+            dash: None,
+        });
+        shapes.push(match frame.projection.outline() {
+            Some(outline) => Shape::Path {
+                runs: frame.to_drawing(vec![outline]),
+                closed: true,
+                stroke: sphere_stroke,
+                fill: None,
+                back: false,
+            },
+            None => Shape::Circle {
+                center: [frame.center.0, frame.center.1],
+                radius: frame.radius,
+                stroke: sphere_stroke,
+                fill: None,
+            },
+        });
+
+        shapes.extend(point_shapes(points, q, &frame));
+        shapes.extend(circle_label_shapes(
+            great_circles,
+            small_circles,
+            points,
+            q,
+            &frame,
+        ));
+
+        Scene {
+            width: SCENE_SIZE,
+            height: SCENE_SIZE,
+            background: BACKGROUND,
+            shapes,
+        }
+    }
+}
+
+fn grid_shapes(q: Quaternion, grid: &GridConfig, frame: &Frame) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    let color = Color::parse(&grid.color).unwrap_or(Color::rgb(0x6b, 0x8e, 0x23));
+    let stroke = Stroke {
+        color: color.with_alpha(grid.opacity),
+        width: 1.0,
+        dash: grid.dashed.then_some(6.0),
+    };
+
+    for line in grid.lines() {
+        let rotated = line
+            .iter()
+            .map(|&p| q.rotate_point_active(p))
+            .collect::<Vec<_>>();
+        shapes.extend(frame.polyline(&rotated, stroke, grid.opacity / 3.0));
+    }
+
+    for (p, text) in grid.labels() {
+        let pt = q.rotate_point_active(p);
+        let Some(position) = frame.project_point(pt) else {
+            continue;
+        };
+        let opacity = if frame.projection.is_front(pt) {
+            (grid.opacity * 2.5).min(1.0)
+        } else {
+            grid.opacity / 3.0
+        };
+        shapes.push(Shape::Text {
+            position,
+            text,
+            size: 14.0,
+            bold: false,
+            anchor: Anchor::Middle,
+            color: color.with_alpha(opacity),
+        });
+    }
+    shapes
+}
+
+fn great_circle_shapes(
+    great_circles: &[GreatCircle],
+    points: &[Point],
+    q: Quaternion,
+    frame: &Frame,
+) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+
+    for gc in great_circles {
+        let color = gc.color.as_deref().and_then(Color::parse);
+        let stroke = Stroke {
+            color: color.unwrap_or(GREAT_CIRCLE_COLOR).with_alpha(0.9),
+            width: 3.0,
+            dash: None,
+        };
+        let (v, w) = circle_basis(points[gc.pole].absolute);
+        let circle = (0..=GRID_RESOLUTION)
+            .map(|i| {
+                let theta = (i as f64) * std::f64::consts::TAU / (GRID_RESOLUTION as f64);
+                let pt = normalize(add(scale_vec(v, theta.cos()), scale_vec(w, theta.sin())));
+                q.rotate_point_active(pt)
+            })
+            .collect::<Vec<_>>();
+        shapes.extend(frame.polyline(&circle, stroke, BACK_OPACITY));
+    }
+    shapes
+}
+
+fn small_circle_shapes(
+    small_circles: &[SmallCircle],
+    points: &[Point],
+    q: Quaternion,
+    frame: &Frame,
+) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+
+    for sc in small_circles {
+        let color = sc.color.as_deref().and_then(Color::parse);
+        let stroke = Stroke {
+            color: color.unwrap_or(SMALL_CIRCLE_COLOR).with_alpha(0.9),
+            width: 3.0,
+            dash: None,
+        };
+        let pole = points[sc.pole].absolute;
+        let d = sc.plane_distance;
+        let r = (1.0 - d * d).sqrt();
+        let (v, w) = circle_basis(pole);
+        let circle = (0..=GRID_RESOLUTION)
+            .map(|i| {
+                let theta = (i as f64) * std::f64::consts::TAU / (GRID_RESOLUTION as f64);
+                let pt = add(scale_vec(v, theta.cos() * r), scale_vec(w, theta.sin() * r));
+                let pt = normalize(add(pt, scale_vec(pole, d)));
+                q.rotate_point_active(pt)
+            })
+            .collect::<Vec<_>>();
+        shapes.extend(frame.polyline(&circle, stroke, BACK_OPACITY));
+    }
+    shapes
+}
+
+fn arc_shapes(
+    arcs: &[(usize, usize)],
+    points: &[Point],
+    q: Quaternion,
+    frame: &Frame,
+) -> Vec<Shape> {
+    let stroke = Stroke {
+        color: ARC_COLOR,
+        width: 4.0,
+        dash: None,
+    };
+    let mut shapes = Vec::new();
+
+    for (a, b) in arcs {
+        let pa = q.rotate_point_active(points[*a].absolute);
+        let pb = q.rotate_point_active(points[*b].absolute);
+
+        let angle = dot(pa, pb).clamp(-1.0, 1.0).acos();
+        let sin_angle = angle.sin();
+
+        let arc = (0..=ARC_RESOLUTION)
+            .map(|i| {
+                let t = i as f64 / ARC_RESOLUTION as f64;
+                let coeff_a = ((1.0 - t) * angle).sin() / sin_angle;
+                let coeff_b = (t * angle).sin() / sin_angle;
+                normalize(add(scale_vec(pa, coeff_a), scale_vec(pb, coeff_b)))
+            })
+            .collect::<Vec<_>>();
+        shapes.extend(frame.polyline(&arc, stroke, BACK_OPACITY));
+    }
+    shapes
+}
+
+fn trace_shapes(traces: &[Trace], q: Quaternion, frame: &Frame) -> Vec<Shape> {
+    let stroke = Stroke {
+        color: TRACE_COLOR,
+        width: 3.0,
+        dash: None,
+    };
+    traces
+        .iter()
+        .flat_map(|trace| {
+            let rotated = trace
+                .path
+                .iter()
+                .map(|&p| q.rotate_point_active(p))
+                .collect::<Vec<_>>();
+            frame.polyline(&rotated, stroke, BACK_OPACITY)
+        })
+        .collect()
+}
+
+fn point_shapes(points: &[Point], q: Quaternion, frame: &Frame) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+
+    // Back points first, so that front points are painted over them
+    for front in [false, true] {
+        for point in points {
+            if point.hidden {
+                continue;
+            }
+            let p = q.rotate_point_active(point.absolute);
+            if frame.projection.is_front(p) != front {
+                continue;
+            }
+            let Some([x, y]) = frame.project_point(p) else {
+                continue;
+            };
+            let opacity = if front { 1.0 } else { BACK_OPACITY };
+            let radius = if point.name.is_empty() {
+                POINT_RADIUS_SMALL
+            } else {
+                POINT_RADIUS_LARGE
+            };
+            shapes.push(Shape::Circle {
+                center: [x, y],
+                radius,
+                stroke: None,
+                fill: Some(
+                    point
+                        .color
+                        .as_deref()
+                        .and_then(Color::parse)
+                        .unwrap_or(POINT_COLOR)
+                        .with_alpha(opacity),
+                ),
+            });
+            if !point.name.is_empty() {
+                shapes.push(Shape::Text {
+                    position: [x + 16.0, y - 12.0],
+                    text: point.name.clone(),
+                    size: 28.0,
+                    bold: true,
+                    anchor: Anchor::Start,
+                    color: LABEL_COLOR.with_alpha(opacity),
+                });
+            }
+        }
+    }
+
+    shapes
+}
+
+fn circle_label_shapes(
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+    points: &[Point],
+    q: Quaternion,
+    frame: &Frame,
+) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    let (width, height) = (frame.center.0 * 2.0, frame.center.1 * 2.0);
+
+    // Great circle labels
+    for gc in great_circles.iter().filter(|gc| !gc.name.is_empty()) {
+        let (v, _) = circle_basis(points[gc.pole].absolute);
+
+        // Try both theta=0 and theta=PI, pick the one with max z (frontmost)
+        let [a, b] = [v, scale_vec(v, -1.0)].map(|pt| q.rotate_point_active(pt));
+        let pt = if a[2] >= b[2] { a } else { b };
+
+        if let Some([mut x, mut y]) = frame.project_point(pt) {
+            // Offset label outward from the sphere edge
+            let label_offset = 24.0;
+            let label_width = 120.0;
+            let label_height = 32.0;
+            x += pt[0] * label_offset;
+            y += pt[1] * label_offset;
+            x = x.clamp(8.0, width - label_width - 8.0);
+            y = y.clamp(label_height, height - 8.0);
+            shapes.push(Shape::Text {
+                position: [x, y],
+                text: gc.name.clone(),
+                size: 28.0,
+                bold: true,
+                anchor: Anchor::Start,
+                color: LABEL_COLOR,
+            });
+        }
+    }
+
+    // Small circle labels
+    for sc in small_circles.iter().filter(|sc| !sc.name.is_empty()) {
+        let pole = points[sc.pole].absolute;
+        let d = sc.plane_distance;
+        let r = (1.0 - d * d).sqrt();
+        let (v, w) = circle_basis(pole);
+        let acos_d = (-d).clamp(-1.0, 1.0).acos();
+
+        // Of the two candidates, the one closest to the limb
+        let [a, b] = [acos_d, std::f64::consts::PI - acos_d].map(|theta| {
+            let pt = add(scale_vec(v, theta.cos() * r), scale_vec(w, theta.sin() * r));
+            q.rotate_point_active(normalize(add(pt, scale_vec(pole, d))))
+        });
+        let pt = if a[2].abs() <= b[2].abs() { a } else { b };
+
+        if let Some([mut x, mut y]) = frame.project_point(pt) {
+            let label_offset = 20.0;
+            let label_width = 80.0;
+            let label_height = 20.0;
+            x += pt[0] * label_offset;
+            y += pt[1] * label_offset;
+            x = x.clamp(8.0, width - label_width - 8.0);
+            y = y.clamp(label_height, height - 8.0);
+            let opacity = if frame.projection.is_front(pt) {
+                1.0
+            } else {
+                BACK_OPACITY
+            };
+            shapes.push(Shape::Text {
+                position: [x, y],
+                text: sc.name.clone(),
+                size: 18.0,
+                bold: false,
+                anchor: Anchor::Start,
+                color: SMALL_LABEL_COLOR.with_alpha(opacity),
+            });
+        }
+    }
+
+    shapes
+}
@@ -0,0 +1,96 @@
+use super::scene::*;
+use crate::*;
+
+fn paint(kind: &str, color: Option<Color>) -> String {
+    match color {
+        Some(color) if color.a < 1.0 => format!(
+            r#" {kind}="{}" {kind}-opacity="{:.2}""#,
+            color.hex(),
+            color.a
+        ),
+        Some(color) => format!(r#" {kind}="{}""#, color.hex()),
+        None => format!(r#" {kind}="none""#),
+    }
+}
+
+fn stroke_attributes(stroke: Option<Stroke>) -> String {
+    let mut attributes = paint("stroke", stroke.map(|s| s.color));
+    if let Some(stroke) = stroke {
+        attributes.push_str(&format!(r#" stroke-width="{}""#, stroke.width));
+        if let Some(dash) = stroke.dash {
+            attributes.push_str(&format!(r#" stroke-dasharray="{dash},{dash}""#));
+        }
+    }
+    attributes
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the scene as a standalone SVG document
+pub fn scene_to_svg(scene: &Scene) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = scene.width,
+        h = scene.height
+    );
+
+    for shape in &scene.shapes {
+        svg.push('\n');
+        match shape {
+            Shape::Path {
+                runs,
+                closed,
+                stroke,
+                fill,
+                ..
+            } => {
+                let close = if *closed { " Z" } else { "" };
+                svg.push_str(&format!(
+                    r#"<path d="{}{close}"{}{}/>"#,
+                    runs_to_path(runs, (0.0, 0.0), 1.0),
+                    paint("fill", *fill),
+                    stroke_attributes(*stroke)
+                ));
+            }
+            Shape::Circle {
+                center: [x, y],
+                radius,
+                stroke,
+                fill,
+            } => {
+                svg.push_str(&format!(
+                    r#"<circle cx="{x:.2}" cy="{y:.2}" r="{radius:.2}"{}{}/>"#,
+                    paint("fill", *fill),
+                    stroke_attributes(*stroke)
+                ));
+            }
+            Shape::Text {
+                position: [x, y],
+                text,
+                size,
+                bold,
+                anchor,
+                color,
+            } => {
+                let weight = if *bold { "bold" } else { "normal" };
+                let anchor = match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                };
+                svg.push_str(&format!(
+                    r#"<text x="{x:.2}" y="{y:.2}" font-family="sans-serif" font-size="{size}" font-weight="{weight}" text-anchor="{anchor}"{}>{}</text>"#,
+                    paint("fill", Some(*color)),
+                    escape(text)
+                ));
+            }
+        }
+    }
+
+    svg.push_str("\n</svg>");
+    svg
+}
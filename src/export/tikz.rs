@@ -0,0 +1,252 @@
+use super::scene::*;
+
+const POINTS_PER_CM: f64 = 72.27 / 2.54;
+/// Coordinates written on one line of a `\path` command
+const COORDINATES_PER_LINE: usize = 6;
+/// Dash pattern of back paths that are not dashed already, in TeX points
+const BACK_DASH: f64 = 2.0;
+
+/// Settings of a TikZ export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TikzOptions {
+    /// Width of the picture on the page, in centimeters
+    pub width_cm: f64,
+    /// Wraps the picture in a `standalone` document that compiles on its own
+    pub standalone: bool,
+}
+
+impl Default for TikzOptions {
+    fn default() -> Self {
+        Self {
+            width_cm: 8.0,
+            standalone: false,
+        }
+    }
+}
+
+/// Escapes the characters that are special in LaTeX text
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Colors used by the scene, each defined once at the top of the picture
+struct Palette(Vec<Color>);
+
+impl Palette {
+    fn name(&self, color: Color) -> String {
+        let index = self
+            .0
+            .iter()
+            .position(|c| c.hex() == color.hex())
+            .unwrap_or_default();
+        // Letters only, as digits are not allowed in color names used by `\definecolor`
+        let mut suffix = Vec::new();
+        let mut n = index + 1;
+        while n > 0 {
+            n -= 1;
+            suffix.push((b'A' + (n % 26) as u8) as char);
+            n /= 26;
+        }
+        format!("sphere{}", suffix.iter().rev().collect::<String>())
+    }
+}
+
+struct Writer {
+    tikz: String,
+    palette: Palette,
+    /// Length of a drawing unit, in centimeters
+    scale: f64,
+    center: [f64; 2],
+}
+
+impl Writer {
+    /// Drawing units, with y pointing down, to centimeters around the sphere's center
+    fn coordinate(&self, [x, y]: [f64; 2]) -> String {
+        format!(
+            "({:.3},{:.3})",
+            (x - self.center[0]) * self.scale,
+            (self.center[1] - y) * self.scale
+        )
+    }
+
+    fn points(&self, length: f64) -> f64 {
+        length * self.scale * POINTS_PER_CM
+    }
+
+    fn options(&self, stroke: Option<Stroke>, fill: Option<Color>, back: bool) -> String {
+        let mut options = Vec::new();
+        if let Some(stroke) = stroke {
+            options.push(format!("draw={}", self.palette.name(stroke.color)));
+            options.push(format!("line width={:.2}pt", self.points(stroke.width)));
+            if stroke.color.a < 1.0 {
+                options.push(format!("draw opacity={:.2}", stroke.color.a));
+            }
+            match stroke.dash {
+                Some(dash) => {
+                    let dash = self.points(dash);
+                    options.push(format!("dash pattern=on {dash:.2}pt off {dash:.2}pt"));
+                }
+                None if back => {
+                    options.push(format!("dash pattern=on {BACK_DASH}pt off {BACK_DASH}pt"))
+                }
+                None => {}
+            }
+        }
+        if let Some(fill) = fill {
+            options.push(format!("fill={}", self.palette.name(fill)));
+            if fill.a < 1.0 {
+                options.push(format!("fill opacity={:.2}", fill.a));
+            }
+        }
+        options.join(", ")
+    }
+
+    fn shape(&mut self, shape: &Shape) {
+        match shape {
+            Shape::Path {
+                runs,
+                closed,
+                stroke,
+                fill,
+                back,
+            } => {
+                let mut path = format!("  \\path[{}]", self.options(*stroke, *fill, *back));
+                for run in runs.iter().filter(|run| run.len() > 1) {
+                    for (i, &point) in run.iter().enumerate() {
+                        if i % COORDINATES_PER_LINE == 0 {
+                            path.push_str("\n    ");
+                        } else {
+                            path.push(' ');
+                        }
+                        if i > 0 {
+                            path.push_str("-- ");
+                        }
+                        path.push_str(&self.coordinate(point));
+                    }
+                    if *closed {
+                        path.push_str(" -- cycle");
+                    }
+                }
+                path.push_str(";\n");
+                self.tikz.push_str(&path);
+            }
+            Shape::Circle {
+                center,
+                radius,
+                stroke,
+                fill,
+            } => {
+                self.tikz.push_str(&format!(
+                    "  \\path[{}] {} circle[radius={:.3}];\n",
+                    self.options(*stroke, *fill, false),
+                    self.coordinate(*center),
+                    radius * self.scale
+                ));
+            }
+            Shape::Text {
+                position,
+                text,
+                bold,
+                anchor,
+                color,
+                ..
+            } => {
+                let anchor = match anchor {
+                    Anchor::Start => "base west",
+                    Anchor::Middle => "base",
+                };
+                let font = if *bold {
+                    r"\footnotesize\bfseries"
+                } else {
+                    r"\footnotesize"
+                };
+                let mut options = format!(
+                    "anchor={anchor}, inner sep=0pt, font={font}, text={}",
+                    self.palette.name(*color)
+                );
+                if color.a < 1.0 {
+                    options.push_str(&format!(", text opacity={:.2}", color.a));
+                }
+                self.tikz.push_str(&format!(
+                    "  \\node[{options}] at {} {{{}}};\n",
+                    self.coordinate(*position),
+                    latex_escape(text)
+                ));
+            }
+        }
+    }
+}
+
+/// Writes the scene as a `tikzpicture`. Paths on the far side of the sphere are dashed,
+/// as is usual in printed diagrams; labels use the document's font.
+pub fn scene_to_tikz(scene: &Scene, options: &TikzOptions) -> String {
+    let mut colors: Vec<Color> = Vec::new();
+    let mut add = |color: Color| {
+        if !colors.iter().any(|c| c.hex() == color.hex()) {
+            colors.push(color);
+        }
+    };
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Path { stroke, fill, .. } | Shape::Circle { stroke, fill, .. } => {
+                if let Some(stroke) = stroke {
+                    add(stroke.color);
+                }
+                if let Some(fill) = fill {
+                    add(*fill);
+                }
+            }
+            Shape::Text { color, .. } => add(*color),
+        }
+    }
+
+    let mut writer = Writer {
+        tikz: String::new(),
+        palette: Palette(colors),
+        scale: options.width_cm.max(0.1) / scene.width,
+        center: [scene.width / 2.0, scene.height / 2.0],
+    };
+
+    if options.standalone {
+        writer.tikz.push_str(
+            "\\documentclass[tikz]{standalone}\n\\usepackage[utf8]{inputenc}\n\\begin{document}\n",
+        );
+    }
+    writer.tikz.push_str("\\begin{tikzpicture}\n");
+    for color in &writer.palette.0 {
+        let name = writer.palette.name(*color);
+        let hex = color.hex().trim_start_matches('#').to_uppercase();
+        writer
+            .tikz
+            .push_str(&format!("  \\definecolor{{{name}}}{{HTML}}{{{hex}}}\n"));
+    }
+    // Fixes the bounding box, so that the picture keeps its size whatever is drawn
+    writer.tikz.push_str(&format!(
+        "  \\useasboundingbox {} rectangle {};\n",
+        writer.coordinate([0.0, scene.height]),
+        writer.coordinate([scene.width, 0.0])
+    ));
+
+    for shape in &scene.shapes {
+        writer.shape(shape);
+    }
+
+    writer.tikz.push_str("\\end{tikzpicture}\n");
+    if options.standalone {
+        writer.tikz.push_str("\\end{document}\n");
+    }
+    writer.tikz
+}
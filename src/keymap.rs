@@ -0,0 +1,435 @@
+use crate::*;
+use dioxus::html::input_data::keyboard_types::Modifiers;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const KEYMAP_KEY: &str = "celestialsphere-keymap";
+
+/// Something a keyboard shortcut does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum KeyAction {
+    ClearSelection,
+    DeleteSelected,
+    Antipode,
+    GreatCircleAroundPole,
+    GreatCircleThroughPoints,
+    SmallCircleThroughPoints,
+    SmallCircleAroundPole,
+    ToggleHidden,
+    Group,
+    Ungroup,
+    RotateX,
+    RotateY,
+    RotateZ,
+    LookAtSelected,
+    LookAtPole,
+    ResetView,
+    ShowShortcuts,
+    OpenPalette,
+    SelectTool,
+    PointTool,
+    ArcTool,
+    GreatCircleTool,
+    SmallCircleTool,
+    MeasureTool,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 24] = [
+        KeyAction::ClearSelection,
+        KeyAction::DeleteSelected,
+        KeyAction::Antipode,
+        KeyAction::GreatCircleAroundPole,
+        KeyAction::GreatCircleThroughPoints,
+        KeyAction::SmallCircleThroughPoints,
+        KeyAction::SmallCircleAroundPole,
+        KeyAction::ToggleHidden,
+        KeyAction::Group,
+        KeyAction::Ungroup,
+        KeyAction::RotateX,
+        KeyAction::RotateY,
+        KeyAction::RotateZ,
+        KeyAction::LookAtSelected,
+        KeyAction::LookAtPole,
+        KeyAction::ResetView,
+        KeyAction::ShowShortcuts,
+        KeyAction::OpenPalette,
+        KeyAction::SelectTool,
+        KeyAction::PointTool,
+        KeyAction::ArcTool,
+        KeyAction::GreatCircleTool,
+        KeyAction::SmallCircleTool,
+        KeyAction::MeasureTool,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            KeyAction::ClearSelection => "Deselect the selected point(s)",
+            KeyAction::DeleteSelected => "Remove the selected point(s)",
+            KeyAction::Antipode => "Place the antipode of the selected point(s)",
+            KeyAction::GreatCircleAroundPole => {
+                "Draw the great circle with the selected point as pole"
+            }
+            KeyAction::GreatCircleThroughPoints => {
+                "Draw the great circle through the 2 selected points"
+            }
+            KeyAction::SmallCircleThroughPoints => {
+                "Draw the small circle through the 3 selected points"
+            }
+            KeyAction::SmallCircleAroundPole => {
+                "Draw a small circle around the first selected point, through the second"
+            }
+            KeyAction::ToggleHidden => "Hide/show the selected point(s)",
+            KeyAction::Group => "Group the selected points",
+            KeyAction::Ungroup => "Ungroup the selected points",
+            KeyAction::RotateX => "Rotate the sphere in the x direction",
+            KeyAction::RotateY => "Rotate the sphere in the y direction",
+            KeyAction::RotateZ => "Rotate the sphere in the z direction",
+            KeyAction::LookAtSelected => "Turn the selected point to face you",
+            KeyAction::LookAtPole => "Look down the pole of the selected great circle",
+            KeyAction::ResetView => "Return to the initial view",
+            KeyAction::ShowShortcuts => "Show the keyboard shortcuts",
+            KeyAction::OpenPalette => "Open the command palette",
+            KeyAction::SelectTool => "Use the select tool",
+            KeyAction::PointTool => "Use the point tool",
+            KeyAction::ArcTool => "Use the arc tool",
+            KeyAction::GreatCircleTool => "Use the great circle tool",
+            KeyAction::SmallCircleTool => "Use the small circle tool",
+            KeyAction::MeasureTool => "Use the measure tool",
+        }
+    }
+
+    /// Why the action cannot be used with the current selection, if it cannot
+    pub fn unavailable(self, points: &[Point], state: &State) -> Option<&'static str> {
+        let selected = state.selected();
+        match self {
+            KeyAction::ClearSelection
+            | KeyAction::Antipode
+            | KeyAction::GreatCircleAroundPole
+            | KeyAction::ToggleHidden
+            | KeyAction::LookAtSelected
+                if selected.is_empty() =>
+            {
+                Some("Select a point first")
+            }
+            KeyAction::DeleteSelected if selected.is_empty() => Some("Select a point first"),
+            KeyAction::DeleteSelected
+                if selected
+                    .iter()
+                    .all(|&id| points.get(id).is_none_or(|point| !point.removable)) =>
+            {
+                Some("The selected points cannot be removed")
+            }
+            KeyAction::GreatCircleThroughPoints | KeyAction::SmallCircleAroundPole
+                if selected.len() != 2 =>
+            {
+                Some("Select 2 points")
+            }
+            KeyAction::SmallCircleThroughPoints if selected.len() != 3 => Some("Select 3 points"),
+            KeyAction::Group if selected.len() < 2 => Some("Select 2 or more points"),
+            KeyAction::Ungroup
+                if selected
+                    .iter()
+                    .all(|&id| state.find_group_containing(id).is_none()) =>
+            {
+                Some("No selected point is in a group")
+            }
+            KeyAction::LookAtPole if !matches!(selected.len(), 1 | 2) => {
+                Some("Select a pole, or 2 points on the great circle")
+            }
+            _ => None,
+        }
+    }
+
+    /// Tool the action switches to, if it is a tool action
+    pub fn tool(self) -> Option<Tool> {
+        Tool::ALL.into_iter().find(|tool| tool.action() == self)
+    }
+
+    /// Binding used unless the user changed it
+    pub fn default_binding(self) -> KeyBinding {
+        match self {
+            KeyAction::ClearSelection => KeyBinding::new("Escape"),
+            KeyAction::DeleteSelected => KeyBinding::new("Delete"),
+            KeyAction::Antipode => KeyBinding::new("/"),
+            KeyAction::GreatCircleAroundPole => KeyBinding::new("."),
+            KeyAction::GreatCircleThroughPoints => KeyBinding::new(">"),
+            KeyAction::SmallCircleThroughPoints => KeyBinding::new(","),
+            KeyAction::SmallCircleAroundPole => KeyBinding::new("<"),
+            KeyAction::ToggleHidden => KeyBinding::ctrl("h"),
+            KeyAction::Group => KeyBinding::ctrl("g"),
+            KeyAction::Ungroup => KeyBinding::ctrl("u"),
+            KeyAction::RotateX => KeyBinding::ctrl("a"),
+            KeyAction::RotateY => KeyBinding::ctrl("s"),
+            KeyAction::RotateZ => KeyBinding::ctrl("d"),
+            KeyAction::LookAtSelected => KeyBinding::ctrl("l"),
+            KeyAction::LookAtPole => KeyBinding::ctrl("p"),
+            KeyAction::ResetView => KeyBinding::ctrl("r"),
+            KeyAction::ShowShortcuts => KeyBinding::new("?"),
+            KeyAction::OpenPalette => KeyBinding::ctrl("k"),
+            KeyAction::SelectTool => KeyBinding::new("1"),
+            KeyAction::PointTool => KeyBinding::new("2"),
+            KeyAction::ArcTool => KeyBinding::new("3"),
+            KeyAction::GreatCircleTool => KeyBinding::new("4"),
+            KeyAction::SmallCircleTool => KeyBinding::new("5"),
+            KeyAction::MeasureTool => KeyBinding::new("6"),
+        }
+    }
+}
+
+/// A key together with the modifiers held with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBinding {
+    /// The character typed, in lower case, or the name of a key such as `Delete`
+    pub key: String,
+    #[serde(default)]
+    pub ctrl: bool,
+    #[serde(default)]
+    pub alt: bool,
+    /// Only recorded for named keys; for characters it is part of the character,
+    /// as `>` is Shift + `.` on many layouts
+    #[serde(default)]
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    fn new(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    fn ctrl(key: &str) -> Self {
+        Self {
+            ctrl: true,
+            ..Self::new(key)
+        }
+    }
+
+    /// Binding of a key press, or `None` for a modifier pressed on its own
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Option<Self> {
+        let (key, shift) = match key {
+            Key::Shift | Key::Control | Key::Alt | Key::AltGraph | Key::Meta | Key::CapsLock => {
+                return None
+            }
+            Key::Character(c) => (c.to_lowercase(), false),
+            key => (key.to_string(), modifiers.shift()),
+        };
+        Some(Self {
+            key,
+            ctrl: modifiers.ctrl(),
+            alt: modifiers.alt(),
+            shift,
+        })
+    }
+
+    /// Shortcuts the browser handles before the page sees them. Ctrl or Alt with
+    /// a digit switches tabs, depending on the system.
+    pub fn reserved(&self) -> bool {
+        let digit = self.key.len() == 1 && self.key.chars().all(|c| c.is_ascii_digit());
+        (self.ctrl && !self.alt && matches!(self.key.as_str(), "n" | "t" | "w" | "Tab"))
+            || (self.ctrl != self.alt && digit)
+    }
+
+    /// Whether the key also types a character into names: a single character
+    /// without Ctrl or Alt
+    pub fn types(&self) -> bool {
+        !self.ctrl && !self.alt && self.key.chars().count() == 1
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        match self.key.as_str() {
+            " " => write!(f, "Space"),
+            // Named keys such as `Delete` keep their own spelling
+            key if key.chars().count() == 1 => write!(f, "{}", key.to_uppercase()),
+            key => write!(f, "{key}"),
+        }
+    }
+}
+
+/// Keyboard shortcuts: the defaults with the user's changes, which are kept in
+/// local storage
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Keymap {
+    /// Changed bindings; `None` leaves the action without a shortcut
+    overrides: BTreeMap<KeyAction, Option<KeyBinding>>,
+}
+
+impl Keymap {
+    pub fn load() -> Self {
+        platform::storage_get(KEYMAP_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn store(&self) {
+        let result = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| platform::storage_set(KEYMAP_KEY, &json));
+        if let Err(error) = result {
+            platform::log_error(&format!("Cannot save the keyboard shortcuts: {error}"));
+        }
+    }
+
+    pub fn binding(&self, action: KeyAction) -> Option<KeyBinding> {
+        match self.overrides.get(&action) {
+            Some(binding) => binding.clone(),
+            None => Some(action.default_binding()),
+        }
+    }
+
+    pub fn is_default(&self, action: KeyAction) -> bool {
+        !self.overrides.contains_key(&action)
+    }
+
+    pub fn set(&mut self, action: KeyAction, binding: Option<KeyBinding>) {
+        if binding.as_ref() == Some(&action.default_binding()) {
+            self.overrides.remove(&action);
+        } else {
+            self.overrides.insert(action, binding);
+        }
+        self.store();
+    }
+
+    pub fn reset_all(&mut self) {
+        self.overrides.clear();
+        self.store();
+    }
+
+    /// KeyAction bound to a key press; of actions sharing a binding, the first in
+    /// `KeyAction::ALL` wins
+    pub fn action(&self, binding: &KeyBinding) -> Option<KeyAction> {
+        KeyAction::ALL
+            .into_iter()
+            .find(|&action| self.binding(action).as_ref() == Some(binding))
+    }
+
+    /// Other actions bound to the same key as `action`
+    pub fn conflicts(&self, action: KeyAction) -> Vec<KeyAction> {
+        let Some(binding) = self.binding(action) else {
+            return Vec::new();
+        };
+        KeyAction::ALL
+            .into_iter()
+            .filter(|&other| other != action && self.binding(other) == Some(binding.clone()))
+            .collect()
+    }
+}
+
+/// Keys that edit names rather than run an action, and cannot be remapped
+const TYPING_KEYS: [(&str, &str); 3] = [
+    ("Type", "Name the selected point(s)"),
+    ("Shift + type", "Name the circle whose pole is selected"),
+    (
+        "Backspace",
+        "Delete the last letter of the name (with Shift, of the circle's)",
+    ),
+];
+
+/// Lists every shortcut and lets the user change them. While a shortcut is being
+/// changed, the next key pressed becomes its binding.
+#[component]
+pub fn ShortcutsDialog(mut keymap: Signal<Keymap>, mut show: Signal<bool>) -> Element {
+    let mut recording = use_signal(|| None::<KeyAction>);
+
+    rsx! {
+        div { class: "dialog-backdrop", onclick: move |_| show.set(false),
+            div {
+                class: "dialog shortcuts-dialog",
+                role: "dialog",
+                tabindex: "0",
+                onmounted: move |evt| async move {
+                    let _ = evt.data().set_focus(true).await;
+                },
+                onclick: move |evt| evt.stop_propagation(),
+                onkeydown: move |evt| {
+                    evt.stop_propagation();
+                    let Some(action) = recording() else {
+                        if evt.key() == Key::Escape {
+                            show.set(false);
+                        }
+                        return;
+                    };
+                    if let Some(binding) = KeyBinding::from_event(&evt.key(), evt.modifiers()) {
+                        evt.prevent_default();
+                        keymap.write().set(action, Some(binding));
+                        recording.set(None);
+                    }
+                },
+                h3 { "Keyboard shortcuts" }
+                table { class: "shortcuts-table",
+                    for action in KeyAction::ALL {
+                        tr { key: "{action:?}",
+                            td { "{action.description()}" }
+                            td {
+                                if recording() == Some(action) {
+                                    em { "Press a key…" }
+                                } else if let Some(binding) = keymap.read().binding(action) {
+                                    kbd { "{binding}" }
+                                } else {
+                                    "—"
+                                }
+                                for other in keymap.read().conflicts(action) {
+                                    div { class: "dialog-error", "Also bound to: {other.description()}" }
+                                }
+                                if keymap.read().binding(action).is_some_and(|binding| binding.reserved()) {
+                                    div { class: "dialog-error", "The browser may keep this shortcut" }
+                                }
+                            }
+                            td { class: "shortcut-buttons",
+                                button {
+                                    onclick: move |_| {
+                                        recording.set(if recording() == Some(action) { None } else { Some(action) });
+                                    },
+                                    if recording() == Some(action) { "Cancel" } else { "Change" }
+                                }
+                                button {
+                                    disabled: keymap.read().binding(action).is_none(),
+                                    onclick: move |_| keymap.write().set(action, None),
+                                    "Clear"
+                                }
+                                button {
+                                    disabled: keymap.read().is_default(action),
+                                    onclick: move |_| keymap.write().set(action, Some(action.default_binding())),
+                                    "Reset"
+                                }
+                            }
+                        }
+                    }
+                    for (keys , description) in TYPING_KEYS {
+                        tr { key: "{keys}",
+                            td { "{description}" }
+                            td {
+                                kbd { "{keys}" }
+                            }
+                            td {}
+                        }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        keymap.write().reset_all();
+                        recording.set(None);
+                    },
+                    "Reset all"
+                }
+                button { onclick: move |_| show.set(false), "Close" }
+            }
+        }
+    }
+}
@@ -1,16 +1,47 @@
 pub use dioxus::{html::input_data::MouseButton, prelude::*};
 
+pub mod autosave;
 pub mod circle;
+pub mod command;
+pub mod constraint;
+pub mod construction;
+pub mod context_menu;
+pub mod csv;
 pub mod event;
+pub mod export;
 pub mod file;
+pub mod keymap;
+pub mod palette;
 pub mod panels;
+pub mod platform;
 pub mod point;
-
+pub mod projection;
+pub mod save;
+pub mod script;
+pub mod share;
+pub mod tool;
+pub mod view;
+
+pub use autosave::*;
 pub use circle::*;
+pub use command::*;
+pub use constraint::*;
+pub use construction::*;
+pub use context_menu::*;
+pub use csv::*;
 pub use event::*;
+pub use export::*;
 pub use file::*;
+pub use keymap::*;
+pub use palette::*;
 pub use panels::*;
 pub use point::*;
+pub use projection::*;
+pub use save::*;
+pub use script::*;
+pub use share::*;
+pub use tool::*;
+pub use view::*;
 
 pub const FAVICON: Asset = asset!("/assets/triangle.ico");
 pub const MAIN_CSS: Asset = asset!("/assets/main.css");
@@ -24,9 +55,18 @@ pub struct State {
     pub rotation: Vec3,
     pub quaternion: Quaternion,
     pub show_grid: bool,
+    pub grid: GridConfig,
     pub show_hidden: bool,
     pub show_center: bool,
+    pub projection: Projection,
     pub groups: Vec<Vec<usize>>,
+    pub constraints: Vec<Constraint>,
+    pub traces: Vec<Trace>,
+    pub view_animation: usize,
+    pub rotation_inertia: bool,
+    pub tool: Tool,
+    /// Points added or dragged snap onto nearby great circles
+    pub snap: bool,
 }
 
 impl State {
@@ -37,12 +77,30 @@ impl State {
             rotation: [0.0, 0.0, 0.0],
             quaternion: Quaternion::identity(),
             show_grid: false,
+            grid: GridConfig::default(),
             show_hidden: false,
             show_center: false,
+            projection: Projection::default(),
             groups: vec![],
+            constraints: vec![],
+            traces: vec![],
+            view_animation: 0,
+            rotation_inertia: true,
+            tool: Tool::default(),
+            snap: false,
         }
     }
 
+    /// Starts a new view animation, superseding any running one
+    pub fn begin_view_animation(&mut self) -> usize {
+        self.view_animation += 1;
+        self.view_animation
+    }
+
+    pub fn cancel_view_animation(&mut self) {
+        self.view_animation += 1;
+    }
+
     pub fn selected(&self) -> &[usize] {
         self.selected.as_slice()
     }
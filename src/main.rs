@@ -11,13 +11,42 @@ fn App() -> Element {
     let great_circles = use_signal(Vec::<GreatCircle>::new);
     let small_circles = use_signal(Vec::<SmallCircle>::new);
     let state = use_signal(State::initialize);
+    let keymap = use_signal(Keymap::load);
+    let show_shortcuts = use_signal(|| false);
+    let show_palette = use_signal(|| false);
+    // Offers to restore the previous session before anything is autosaved,
+    // unless the page was opened from a shared link
+    let show_autosaves = use_signal(|| !has_shared_diagram() && !load_snapshots().is_empty());
+    let mut shared_report = use_signal(|| None::<LoadReport>);
+    use_effect(move || {
+        let report = match load_shared_diagram(points, arcs, great_circles, small_circles, state) {
+            Some(Ok(repairs)) if !repairs.is_empty() => Some(LoadReport::Repaired(repairs)),
+            Some(Err(error)) => Some(LoadReport::Failed(error)),
+            _ => None,
+        };
+        shared_report.set(report);
+    });
+    use_autosave(
+        points,
+        arcs,
+        great_circles,
+        small_circles,
+        state,
+        show_autosaves,
+    );
 
     rsx! {
         document::Link { rel: "icon", href: FAVICON }
         document::Link { rel: "stylesheet", href: MAIN_CSS }
 
         SelectionBox { points, state }
-        SlidersPanel { points, state }
+        Toolbar { points, state, keymap }
+        SlidersPanel {
+            points,
+            great_circles,
+            state,
+            show_shortcuts,
+        }
         LeftPanel {
             state,
             points,
@@ -30,6 +59,42 @@ fn App() -> Element {
             great_circles,
             small_circles,
             state,
+            show_autosaves,
+        }
+        CommandConsole {
+            points,
+            arcs,
+            great_circles,
+            small_circles,
+            state,
+        }
+        if show_autosaves() {
+            AutosaveDialog {
+                points,
+                arcs,
+                great_circles,
+                small_circles,
+                state,
+                show: show_autosaves,
+            }
+        }
+        if show_palette() {
+            CommandPalette {
+                points,
+                arcs,
+                great_circles,
+                small_circles,
+                state,
+                keymap,
+                show: show_palette,
+                show_shortcuts,
+            }
+        }
+        if show_shortcuts() {
+            ShortcutsDialog { keymap, show: show_shortcuts }
+        }
+        if let Some(report) = shared_report() {
+            LoadReportDialog { report, on_close: move |_| shared_report.set(None) }
         }
         Sphere {
             points,
@@ -37,6 +102,9 @@ fn App() -> Element {
             great_circles,
             small_circles,
             state,
+            keymap,
+            show_shortcuts,
+            show_palette,
         }
     }
 }
@@ -48,36 +116,81 @@ pub fn Sphere(
     mut great_circles: Signal<Vec<GreatCircle>>,
     mut small_circles: Signal<Vec<SmallCircle>>,
     mut state: Signal<State>,
+    keymap: Signal<Keymap>,
+    show_shortcuts: Signal<bool>,
+    show_palette: Signal<bool>,
 ) -> Element {
     let dragged_point = use_signal(|| None::<usize>);
     let is_rotating = use_signal(|| false);
     let last_rotation_pos = use_signal(|| (0.0, 0.0));
+    // Axis, angular speed and timestamp of the latest rotation drag step
+    let spin = use_signal(|| ([0.0, 0.0, 1.0], 0.0, 0.0));
+    let mut sphere_svg = use_signal(|| None::<std::rc::Rc<MountedData>>);
+    let context_menu = use_signal(|| None::<ContextMenu>);
+    use_effect(move || set_sphere_zoom(state.read().zoom));
 
     let primary_click = move |event: Event<MouseData>| {
-        handle_primary_click(event, points, great_circles, state, dragged_point)
+        handle_primary_click(
+            event,
+            points,
+            arcs,
+            great_circles,
+            small_circles,
+            state,
+            dragged_point,
+        )
+    };
+    let secondary_click = move |event: Event<MouseData>| {
+        handle_secondary_click(
+            event,
+            points,
+            arcs,
+            great_circles,
+            small_circles,
+            state,
+            context_menu,
+        )
+    };
+    let middle_click = move |event: Event<MouseData>| {
+        handle_middle_click(event, state, is_rotating, last_rotation_pos, spin)
     };
-    let secondary_click =
-        move |event: Event<MouseData>| handle_secondary_click(event, points, arcs, state);
-    let middle_click =
-        move |event: Event<MouseData>| handle_middle_click(event, is_rotating, last_rotation_pos);
     let scroll = move |event: Event<WheelData>| handle_scroll(event, state);
     let mouse_move = move |event: Event<MouseData>| {
         handle_mouse_move(
             event,
             points,
             great_circles,
+            small_circles,
             state,
             dragged_point,
             is_rotating,
             last_rotation_pos,
+            spin,
         )
     };
-    let mouse_up =
-        move |event: Event<MouseData>| handle_mouse_up(event, dragged_point, is_rotating);
+    let mouse_up = move |event: Event<MouseData>| {
+        handle_mouse_up(event, points, state, dragged_point, is_rotating, spin)
+    };
     let key_event = move |event: Event<KeyboardData>| {
-        handle_key_event(event, points, arcs, great_circles, small_circles, state)
+        handle_key_event(
+            event,
+            points,
+            arcs,
+            great_circles,
+            small_circles,
+            state,
+            keymap,
+            show_shortcuts,
+            show_palette,
+        )
     };
 
+    let projection = state.read().projection;
+    let outline = projection
+        .outline()
+        .map(|outline| runs_to_path(&[outline], (50.0, 50.0), 25.0) + " Z");
+    let limb_outline = outline.is_none();
+
     rsx! {
         div {
             id: "sphere",
@@ -98,6 +211,16 @@ pub fn Sphere(
                 },
                 onwheel: scroll,
                 svg {
+                    onmounted: move |event| async move {
+                        let svg = event.data();
+                        measure_sphere(&svg).await;
+                        sphere_svg.set(Some(svg));
+                    },
+                    onresize: move |_| async move {
+                        if let Some(svg) = sphere_svg() {
+                            measure_sphere(&svg).await;
+                        }
+                    },
                     width: "95vw",
                     height: "95vh",
                     view_box: "{50.0 - 50.0 / state.read().zoom} {50.0 - 50.0 / state.read().zoom} {100.0 / state.read().zoom} {100.0 / state.read().zoom}",
@@ -105,12 +228,20 @@ pub fn Sphere(
                         cx: "50",
                         cy: "50",
                         r: "25",
-                        stroke: "white",
+                        stroke: if limb_outline { "white" } else { "none" },
                         stroke_width: "0.2",
-                        fill: "rgba(0, 0, 0, 0.4)",
+                        fill: if limb_outline { "rgba(0, 0, 0, 0.4)" } else { "none" },
+                    }
+                    if let Some(outline) = outline {
+                        path {
+                            d: outline,
+                            stroke: "white",
+                            stroke_width: "0.2",
+                            fill: "rgba(0, 0, 0, 0.4)",
+                        }
                     }
                     if state.read().show_grid {
-                        CoordinateGrid { state }
+                        CoordinateGrid { state, points }
                     }
                     if state.read().show_center {
                         circle {
@@ -120,12 +251,13 @@ pub fn Sphere(
                             fill: "blue",
                         }
                     }
-                    GreatCircleDrawer { great_circles, points }
-                    SmallCircleDrawer { small_circles, points }
-                    GreatCircleLabels { great_circles, points }
-                    SmallCircleLabels { small_circles, points }
-                    ArcDrawer { arcs, points }
-                    for (i , x , y , _ , r , opacity , name) in points()
+                    GreatCircleDrawer { great_circles, points, state }
+                    SmallCircleDrawer { small_circles, points, state }
+                    GreatCircleLabels { great_circles, points, state }
+                    SmallCircleLabels { small_circles, points, state }
+                    ArcDrawer { arcs, points, state }
+                    TraceDrawer { state }
+                    for (i , x , y , r , opacity , name , color) in points()
                         .iter()
                         .filter_map(|point| {
                             if point.hidden && !state.read().show_hidden
@@ -133,18 +265,11 @@ pub fn Sphere(
                             {
                                 return None;
                             }
-                            let [x, y, z] = point.rotated;
-                            let opacity = if z > 0.0 { 1.0 } else { 0.4 };
+                            let (x, y) = projection.view_position(point.rotated)?;
+                            let opacity = if projection.is_front(point.rotated) { 1.0 } else { 0.4 };
                             let r = if state.read().selected().contains(&point.id) { 1.0 } else { 0.6 };
-                            Some((
-                                point.id,
-                                x * 25.0 + 50.0,
-                                y * 25.0 + 50.0,
-                                z,
-                                r,
-                                opacity,
-                                &point.name,
-                            ))
+                            let color = point.color.clone().unwrap_or_else(|| "red".to_string());
+                            Some((point.id, x, y, r, opacity, &point.name, color))
                         })
                     {
                         circle {
@@ -152,7 +277,8 @@ pub fn Sphere(
                             cx: "{x}",
                             cy: "{y}",
                             r: "{r}",
-                            fill: "rgba(255, 0, 0, {opacity})",
+                            fill: "{color}",
+                            fill_opacity: "{opacity}",
                         }
                         text {
                             key: "text-{i}",
@@ -168,6 +294,14 @@ pub fn Sphere(
                     }
                 }
             }
+            ContextMenuPanel {
+                menu: context_menu,
+                points,
+                arcs,
+                great_circles,
+                small_circles,
+                state,
+            }
         }
     }
 }
@@ -0,0 +1,139 @@
+use crate::*;
+
+/// Actions whose description contains every word of the query
+fn matching_actions(query: &str) -> Vec<KeyAction> {
+    let query = query.to_lowercase();
+    KeyAction::ALL
+        .into_iter()
+        .filter(|&action| action != KeyAction::OpenPalette)
+        .filter(|action| {
+            let description = action.description().to_lowercase();
+            query
+                .split_whitespace()
+                .all(|word| description.contains(word))
+        })
+        .collect()
+}
+
+/// Searchable list of every keyboard action. Actions that do not fit the current
+/// selection are shown with the reason and cannot be run.
+#[component]
+pub fn CommandPalette(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+    keymap: Signal<Keymap>,
+    mut show: Signal<bool>,
+    mut show_shortcuts: Signal<bool>,
+) -> Element {
+    let mut query = use_signal(String::new);
+    let mut highlighted = use_signal(|| 0usize);
+
+    let actions = matching_actions(&query());
+    let available: Vec<bool> = actions
+        .iter()
+        .map(|action| action.unavailable(&points.read(), &state.read()).is_none())
+        .collect();
+    let mut close = move || {
+        show.set(false);
+        // Give the keyboard back to the sphere
+        document::eval("document.getElementById('sphere')?.focus();");
+    };
+    let mut run = move |action: KeyAction| {
+        close();
+        match action {
+            KeyAction::ShowShortcuts => show_shortcuts.set(true),
+            action => run_key_action(action, points, arcs, great_circles, small_circles, state),
+        }
+    };
+    // Moves the highlight to the next runnable action in the direction given
+    let step = {
+        let available = available.clone();
+        move |from: usize, forward: bool| {
+            let count = available.len();
+            (1..=count)
+                .map(|offset| {
+                    if forward {
+                        (from + offset) % count
+                    } else {
+                        (from + count - offset) % count
+                    }
+                })
+                .find(|&i| available[i])
+                .unwrap_or(from)
+        }
+    };
+    let first_available = available.iter().position(|&available| available);
+    let current = Some(highlighted())
+        .filter(|&i| available.get(i) == Some(&true))
+        .or(first_available);
+
+    rsx! {
+        div { class: "dialog-backdrop", onclick: move |_| close(),
+            div {
+                class: "dialog command-palette",
+                role: "dialog",
+                onclick: move |evt| evt.stop_propagation(),
+                input {
+                    class: "palette-input",
+                    r#type: "text",
+                    placeholder: "Search actions…",
+                    spellcheck: "false",
+                    value: "{query}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.data().set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value());
+                        highlighted.set(0);
+                    },
+                    onkeydown: {
+                        let actions = actions.clone();
+                        move |evt: KeyboardEvent| {
+                            evt.stop_propagation();
+                            match evt.key() {
+                                Key::Escape => close(),
+                                Key::Enter => {
+                                    if let Some(i) = current {
+                                        run(actions[i]);
+                                    }
+                                }
+                                key @ (Key::ArrowDown | Key::ArrowUp) => {
+                                    if let Some(i) = current {
+                                        highlighted.set(step(i, key == Key::ArrowDown));
+                                    }
+                                    evt.prevent_default();
+                                }
+                                _ => {}
+                            }
+                        }
+                    },
+                }
+                ul { class: "palette-list",
+                    for (i , (action , runnable)) in actions.iter().copied().zip(available.iter().copied()).enumerate() {
+                        li {
+                            key: "{action:?}",
+                            class: if !runnable { "palette-unavailable" } else if current == Some(i) { "palette-current" },
+                            onclick: move |_| {
+                                if runnable {
+                                    run(action);
+                                }
+                            },
+                            span { "{action.description()}" }
+                            if let Some(reason) = action.unavailable(&points.read(), &state.read()) {
+                                span { class: "palette-reason", "{reason}" }
+                            } else if let Some(binding) = keymap.read().binding(action) {
+                                kbd { "{binding}" }
+                            }
+                        }
+                    }
+                    if actions.is_empty() {
+                        li { class: "palette-unavailable", "No matching action" }
+                    }
+                }
+            }
+        }
+    }
+}
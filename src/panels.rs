@@ -85,7 +85,7 @@ pub fn SelectionBox(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
                 div {
                     class: "selection-details",
                     style: "max-height: 80vh; overflow-y: auto;",
-                    for Point { id , absolute : [x , y , z] , name , movable , removable , hidden , abs_polar : [theta , phi] , rotated : [rx , ry , rz] , rot_polar : [rtheta , rphi] } in selected_points {
+                    for Point { id , absolute : [x , y , z] , name , movable , removable , hidden , abs_polar : [theta , phi] , rotated : [rx , ry , rz] , rot_polar : [rtheta , rphi] , construction , .. } in selected_points {
                         div { class: "info-box",
                             "Absolute Coordinates:"
                             br {}
@@ -101,12 +101,31 @@ pub fn SelectionBox(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
                             "θ: {-rtheta:.2}, φ: {rphi:.2}"
                             br {}
                             br {}
+                            if let Some(pole) = state.read().grid.reference {
+                                {
+                                    let [lat, lon] = state
+                                        .read()
+                                        .grid
+                                        .frame_coordinates(&points.read(), [x, y, z]);
+                                    rsx! {
+                                        "Grid Frame Coordinates (pole {pole}):"
+                                        br {}
+                                        "lat: {lat:.2}°, lon: {lon:.2}°"
+                                        br {}
+                                        br {}
+                                    }
+                                }
+                            }
                             "ID: {id}"
                             br {}
                             "Name: {name}"
                             if name.is_empty() {
                                 " [unnamed]"
                             }
+                            if let Some(construction) = construction {
+                                br {}
+                                "Defined as: {construction.describe()}"
+                            }
                             br {}
                             br {}
                             div { class: "point-controls",
@@ -153,8 +172,14 @@ pub fn SelectionBox(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
 }
 
 #[component]
-pub fn SlidersPanel(points: Signal<Vec<Point>>, state: Signal<State>) -> Element {
+pub fn SlidersPanel(
+    points: Signal<Vec<Point>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    state: Signal<State>,
+    mut show_shortcuts: Signal<bool>,
+) -> Element {
     let mut change = move || {
+        state.write().cancel_view_animation();
         let q = Quaternion::from_euler_deg(state.read().rotation);
         state.write().quaternion = q;
         for p in points.write().iter_mut() {
@@ -221,6 +246,23 @@ pub fn SlidersPanel(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
                 }
                 span { "{(state.read().zoom * 100.0).round()}%" }
             }
+            div {
+                span { "Projection: " }
+                select {
+                    onchange: move |evt| {
+                        if let Some(projection) = evt.value().parse::<usize>().ok().and_then(|i| Projection::ALL.get(i)) {
+                            state.write().projection = *projection;
+                        }
+                    },
+                    for (i , projection) in Projection::ALL.iter().enumerate() {
+                        option {
+                            value: "{i}",
+                            selected: state.read().projection == *projection,
+                            "{projection.name()}"
+                        }
+                    }
+                }
+            }
             div { class: "checkbox-control",
                 input {
                     r#type: "checkbox",
@@ -232,6 +274,9 @@ pub fn SlidersPanel(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
                 }
                 label { r#for: "grid-toggle", "Show coordinate grid" }
             }
+            if state.read().show_grid {
+                GridSettings { great_circles, state }
+            }
             div { class: "checkbox-control",
                 input {
                     r#type: "checkbox",
@@ -254,6 +299,149 @@ pub fn SlidersPanel(points: Signal<Vec<Point>>, state: Signal<State>) -> Element
                 }
                 label { r#for: "center-toggle", "Show center point" }
             }
+            div { class: "checkbox-control",
+                input {
+                    r#type: "checkbox",
+                    id: "inertia-toggle",
+                    checked: "{state.read().rotation_inertia}",
+                    onchange: move |evt| {
+                        state.write().rotation_inertia = evt.value() == "true";
+                    },
+                }
+                label { r#for: "inertia-toggle", "Rotation momentum" }
+            }
+            button {
+                class: "panel-button",
+                onclick: move |_| reset_view(points, state),
+                "Reset view"
+            }
+            button {
+                class: "panel-button",
+                onclick: move |_| show_shortcuts.set(true),
+                "Keyboard shortcuts"
+            }
+        }
+    }
+}
+
+#[component]
+fn GridSettings(great_circles: Signal<Vec<GreatCircle>>, state: Signal<State>) -> Element {
+    let grid = state.read().grid.clone();
+    let mut set = move |update: fn(&mut GridConfig, f64), value: String| {
+        if let Ok(value) = value.parse::<f64>() {
+            update(&mut state.write().grid, value);
+        }
+    };
+
+    rsx! {
+        div { class: "grid-settings",
+            div {
+                span { "Parallels every" }
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "90",
+                    value: "{grid.lat_step}",
+                    oninput: move |evt| set(|g, v| g.lat_step = v.clamp(1.0, 90.0), evt.value()),
+                }
+                span { "°, meridians every" }
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "180",
+                    value: "{grid.lon_step}",
+                    oninput: move |evt| set(|g, v| g.lon_step = v.clamp(1.0, 180.0), evt.value()),
+                }
+                span { "°" }
+            }
+            div {
+                span { "Latitude" }
+                input {
+                    r#type: "number",
+                    min: "-90",
+                    max: "90",
+                    value: "{grid.lat_min}",
+                    oninput: move |evt| set(|g, v| g.lat_min = v.clamp(-90.0, 90.0), evt.value()),
+                }
+                span { "to" }
+                input {
+                    r#type: "number",
+                    min: "-90",
+                    max: "90",
+                    value: "{grid.lat_max}",
+                    oninput: move |evt| set(|g, v| g.lat_max = v.clamp(-90.0, 90.0), evt.value()),
+                }
+            }
+            div {
+                span { "Longitude" }
+                input {
+                    r#type: "number",
+                    min: "0",
+                    max: "360",
+                    value: "{grid.lon_min}",
+                    oninput: move |evt| set(|g, v| g.lon_min = v.clamp(0.0, 360.0), evt.value()),
+                }
+                span { "to" }
+                input {
+                    r#type: "number",
+                    min: "0",
+                    max: "360",
+                    value: "{grid.lon_max}",
+                    oninput: move |evt| set(|g, v| g.lon_max = v.clamp(0.0, 360.0), evt.value()),
+                }
+            }
+            div {
+                input {
+                    r#type: "color",
+                    value: "{grid.color}",
+                    oninput: move |evt| state.write().grid.color = evt.value(),
+                }
+                span { "Opacity" }
+                input {
+                    r#type: "range",
+                    min: "0.05",
+                    max: "1",
+                    step: "0.05",
+                    value: "{grid.opacity}",
+                    oninput: move |evt| set(|g, v| g.opacity = v, evt.value()),
+                }
+            }
+            div {
+                span { "Equator" }
+                select {
+                    onchange: move |evt| {
+                        state.write().grid.reference = evt.value().parse::<usize>().ok();
+                    },
+                    option { value: "", selected: grid.reference.is_none(), "Absolute frame" }
+                    for gc in great_circles.read().iter() {
+                        option {
+                            value: "{gc.pole}",
+                            selected: grid.reference == Some(gc.pole),
+                            if gc.name.is_empty() {
+                                "Great circle of pole {gc.pole}"
+                            } else {
+                                "{gc.name}"
+                            }
+                        }
+                    }
+                }
+            }
+            div {
+                input {
+                    r#type: "checkbox",
+                    id: "grid-labels",
+                    checked: "{grid.show_labels}",
+                    onchange: move |evt| state.write().grid.show_labels = evt.value() == "true",
+                }
+                label { r#for: "grid-labels", "Labels" }
+                input {
+                    r#type: "checkbox",
+                    id: "grid-dashed",
+                    checked: "{grid.dashed}",
+                    onchange: move |evt| state.write().grid.dashed = evt.value() == "true",
+                }
+                label { r#for: "grid-dashed", "Dashed" }
+            }
         }
     }
 }
@@ -265,109 +453,163 @@ pub fn LeftPanel(
     great_circles: Signal<Vec<GreatCircle>>,
     small_circles: Signal<Vec<SmallCircle>>,
 ) -> Element {
+    // New constraints move the last selected point to satisfy them
+    let mut add_constraint = move |constraint: Constraint| {
+        state.write().constraints.push(constraint);
+        let q = state.read().quaternion;
+        let constraints = state.read().constraints.clone();
+        let mut pinned = state.read().selected().to_vec();
+        pinned.pop();
+        solve_constraints(
+            &mut points.write(),
+            &constraints,
+            &great_circles.read(),
+            &small_circles.read(),
+            &state.read().groups,
+            &pinned,
+            q,
+        );
+        update_constructions(&mut points.write(), &mut small_circles.write(), q);
+        record_traces(&mut state.write().traces, &points.read());
+    };
+    let selected = state.read().selected().to_vec();
+    let label = |id: usize| point_label(&points.read(), id);
+    let is_circle_pole = |id: usize| {
+        great_circles.read().iter().any(|gc| gc.pole == id)
+            || small_circles.read().iter().any(|sc| sc.pole == id)
+    };
+
     rsx! {
         div { class: "left-info-boxes-container",
-            // Group information box
-            if state.read().selected().len() > 1 {
-                if let Some(group_members) = state
-                    .read()
-                    .find_group_containing(state.read().selected()[0])
-                    .map(|group_idx| state.read().groups[group_idx].clone())
-                {
-                    if group_members.iter().all(|&id| state.read().selected().contains(&id)) {
-                        div { class: "info-box",
-                            h3 { "Group Information" }
-                            "Group size: {group_members.len()} points"
-                            br {}
-                            {
-                                let member_ids = group_members
-                                    .iter()
-                                    .map(|id| id.to_string())
-                                    .collect::<Vec<_>>()
-                                    .join(", ");
-                                format!("Group member ids: {}", member_ids)
-                            }
-                            br {}
-                            {
-                                let member_names = group_members
-                                    .iter()
-                                    .map(|&id| {
-                                        let name = &points.read()[id].name;
-                                        if name.is_empty() { "[unnamed]".to_string() } else { name.clone() }
-                                    })
-                                    .collect::<Vec<_>>()
-                                    .join(", ");
-                                format!("Group member names: {}", member_names)
-                            }
-                        }
+            for (i , section) in selection_sections(
+                    &points.read(),
+                    &great_circles.read(),
+                    &small_circles.read(),
+                    &state.read(),
+                )
+                .into_iter()
+                .enumerate()
+            {
+                div { key: "measurement-{i}", class: "info-box",
+                    h3 { "{section.title}" }
+                    for (label , value) in section.rows {
+                        "{label}: {value}"
+                        br {}
                     }
                 }
             }
 
-            if let &[pole] = state.read().selected() {
-                if let Some(gc) = great_circles().iter().find(|gc| gc.pole == pole) {
-                    div { class: "info-box",
-                        h3 { "Great Circle" }
-                        "Pole ID: {pole}"
-                        br {}
-                        "Name: {gc.name}"
-                        if gc.name.is_empty() {
-                            " [unnamed]"
+            if selected.len() == 1 || !state.read().traces.is_empty() {
+                div { class: "info-box constraints-box",
+                    h3 { "Locus Traces" }
+                    if let &[id] = selected.as_slice() {
+                        if !state.read().traces.iter().any(|t| t.point == Some(id) && t.recording) {
+                            button {
+                                class: "panel-button",
+                                onclick: move |_| {
+                                    let trace = Trace::new(&points.read()[id]);
+                                    state.write().traces.push(trace);
+                                },
+                                "Trace point {id}"
+                            }
                         }
                     }
-                }
-                if let Some(sc) = small_circles.read().iter().find(|sc| sc.pole == pole) {
-                    div { class: "info-box",
-                        h3 { "Small Circle" }
-                        "Pole ID: {pole}"
-                        br {}
-                        "Name: {sc.name}"
-                        if sc.name.is_empty() {
-                            " [unnamed]"
+                    for (i , trace) in state.read().traces.iter().enumerate() {
+                        div { key: "trace-{i}", class: "constraint-row",
+                            if let Some(point) = trace.point {
+                                span { "Point {point} ({trace.path.len()} vertices)" }
+                                button {
+                                    class: "panel-button",
+                                    onclick: move |_| {
+                                        let recording = state.read().traces[i].recording;
+                                        state.write().traces[i].recording = !recording;
+                                    },
+                                    if trace.recording {
+                                        "Pause"
+                                    } else {
+                                        "Resume"
+                                    }
+                                }
+                                button {
+                                    class: "panel-button",
+                                    title: "Clear recorded path",
+                                    onclick: move |_| {
+                                        let start = points.read()[point].absolute;
+                                        state.write().traces[i].path = vec![start];
+                                    },
+                                    "Clear"
+                                }
+                            } else {
+                                span { "Deleted point ({trace.path.len()} vertices)" }
+                            }
+                            button {
+                                class: "panel-button",
+                                title: "Remove trace",
+                                onclick: move |_| {
+                                    state.write().traces.remove(i);
+                                },
+                                "×"
+                            }
                         }
-                        br {}
-                        "Plane Distance: {sc.plane_distance:.4}"
-                        br {}
-                        "Radius: {(1.0 - sc.plane_distance.powi(2)).sqrt():.2}"
                     }
                 }
             }
-            if let Some([a, b, c, aa, ab, ac, e]) = 'block: {
-                let &[a, b, c] = state.read().selected() else { break 'block None };
-                let a_pos = points()[a].absolute;
-                let b_pos = points()[b].absolute;
-                let c_pos = points()[c].absolute;
-                let side_a = arc_distance(b_pos, c_pos);
-                let side_b = arc_distance(a_pos, c_pos);
-                let side_c = arc_distance(a_pos, b_pos);
-                let [angle_a, angle_b, angle_c] = calculate_angle(side_a, side_b, side_c);
-                let a = side_a.to_degrees();
-                let b = side_b.to_degrees();
-                let c = side_c.to_degrees();
-                let aa = angle_a.to_degrees();
-                let ab = angle_b.to_degrees();
-                let ac = angle_c.to_degrees();
-                let e = aa + ab + ac - 180.0;
-                Some([a, b, c, aa, ab, ac, e])
-            }
-            {
-                div { class: "info-box",
-                    h3 { "Spherical Triangle" }
-                    "Side a: {a:.4}°"
-                    br {}
-                    "Side b: {b:.4}°"
-                    br {}
-                    "Side c: {c:.4}°"
-                    br {}
-                    br {}
-                    "Angle A: {aa:.4}°"
-                    br {}
-                    "Angle B: {ab:.4}°"
-                    br {}
-                    "Angle C: {ac:.4}°"
-                    br {}
-                    br {}
-                    "Spherical Excess: {e:.4}°"
+
+            if (2..=4).contains(&selected.len()) || !state.read().constraints.is_empty() {
+                div { class: "info-box constraints-box",
+                    h3 { "Constraints" }
+                    match *selected.as_slice() {
+                        [a, b] => rsx! {
+                            button {
+                                class: "panel-button",
+                                onclick: move |_| {
+                                    let angle = arc_distance(points.read()[a].absolute, points.read()[b].absolute);
+                                    add_constraint(Constraint::Distance { a, b, angle });
+                                },
+                                "Fix distance {label(a)}–{label(b)}"
+                            }
+                            if is_circle_pole(b) {
+                                button {
+                                    class: "panel-button",
+                                    onclick: move |_| add_constraint(Constraint::OnCircle { point: a, pole: b }),
+                                    "Keep {label(a)} on circle of {label(b)}"
+                                }
+                            }
+                        },
+                        [a, b, c] => rsx! {
+                            button {
+                                class: "panel-button",
+                                onclick: move |_| add_constraint(Constraint::RightAngle { vertex: a, a: b, b: c }),
+                                "Right angle at {label(a)}"
+                            }
+                            button {
+                                class: "panel-button",
+                                onclick: move |_| add_constraint(Constraint::EqualSides { a, b, c: a, d: c }),
+                                "Equal sides {label(a)}–{label(b)} = {label(a)}–{label(c)}"
+                            }
+                        },
+                        [a, b, c, d] => rsx! {
+                            button {
+                                class: "panel-button",
+                                onclick: move |_| add_constraint(Constraint::EqualSides { a, b, c, d }),
+                                "Equal sides {label(a)}–{label(b)} = {label(c)}–{label(d)}"
+                            }
+                        },
+                        _ => rsx! {},
+                    }
+                    for (i , constraint) in state.read().constraints.iter().enumerate() {
+                        div { key: "constraint-{i}", class: "constraint-row",
+                            span { "{constraint.describe(&points.read())}" }
+                            button {
+                                class: "panel-button",
+                                title: "Remove constraint",
+                                onclick: move |_| {
+                                    state.write().constraints.remove(i);
+                                },
+                                "×"
+                            }
+                        }
+                    }
                 }
             }
         }
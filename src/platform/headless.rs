@@ -0,0 +1,129 @@
+//! Backend of native builds without the desktop app, such as the `render` tool:
+//! there is no window to show dialogs in and nowhere to keep settings
+
+use super::OpenedFile;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const UNAVAILABLE: &str = "Not available outside the browser and the desktop app";
+
+/// Milliseconds since the epoch
+pub fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
+/// Waits without blocking the executor: a helper thread sleeps and wakes the
+/// task. There is no async runtime to provide timers in these builds.
+pub async fn sleep_ms(ms: u32) {
+    Sleep {
+        deadline: Instant::now() + Duration::from_millis(ms.into()),
+        started: false,
+    }
+    .await
+}
+
+struct Sleep {
+    deadline: Instant,
+    started: bool,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Poll::Ready(());
+        }
+        if !self.started {
+            self.started = true;
+            let waker = cx.waker().clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(remaining);
+                waker.wake();
+            });
+        }
+        Poll::Pending
+    }
+}
+
+pub fn log_error(message: &str) {
+    eprintln!("{message}");
+}
+
+/// Date and time of a timestamp in milliseconds since the epoch, as `2024-03-01 14:05 UTC`
+pub fn format_time(time: f64) -> String {
+    super::format_utc(time)
+}
+
+pub fn storage_get(_key: &str) -> Option<String> {
+    None
+}
+
+pub fn storage_set(_key: &str, _value: &str) -> Result<(), String> {
+    Err(UNAVAILABLE.to_string())
+}
+
+pub fn storage_remove(_key: &str) {}
+
+pub fn save_file(_content: &[u8], filename: &str) {
+    log_error(&format!("Cannot save {filename}: {UNAVAILABLE}"));
+}
+
+pub fn open_file(_extensions: &[&str], on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {
+    on_open(Err(UNAVAILABLE.to_string()));
+}
+
+pub fn recent_files() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn open_recent(_path: &str, on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {
+    on_open(Err(UNAVAILABLE.to_string()));
+}
+
+pub fn app_address() -> Result<String, String> {
+    Err(UNAVAILABLE.to_string())
+}
+
+pub fn location_fragment() -> Option<String> {
+    None
+}
+
+pub fn clear_location_fragment() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{mpsc, Arc};
+    use std::task::Wake;
+
+    struct Notify(std::sync::Mutex<mpsc::Sender<()>>);
+
+    impl Wake for Notify {
+        fn wake(self: Arc<Self>) {
+            self.0.lock().unwrap().send(()).unwrap();
+        }
+    }
+
+    #[test]
+    fn sleeping_does_not_block_the_caller() {
+        let (sender, receiver) = mpsc::channel();
+        let waker = Arc::new(Notify(std::sync::Mutex::new(sender))).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut sleep = Box::pin(sleep_ms(50));
+
+        let start = Instant::now();
+        assert!(sleep.as_mut().poll(&mut cx).is_pending());
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        receiver.recv().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(sleep.as_mut().poll(&mut cx).is_ready());
+    }
+}
@@ -0,0 +1,96 @@
+//! Services that differ between the browser and the desktop app: files, storage,
+//! time and the page address. The desktop backend is used with the `desktop`
+//! feature, the browser backend in WebAssembly builds, and a headless one in
+//! other native builds, such as the `render` tool and the tests.
+
+#[cfg(all(not(feature = "desktop"), not(target_arch = "wasm32")))]
+mod headless;
+#[cfg(feature = "desktop")]
+mod native;
+#[cfg(all(not(feature = "desktop"), target_arch = "wasm32"))]
+mod web;
+
+#[cfg(all(not(feature = "desktop"), not(target_arch = "wasm32")))]
+pub use headless::*;
+#[cfg(feature = "desktop")]
+pub use native::*;
+#[cfg(all(not(feature = "desktop"), target_arch = "wasm32"))]
+pub use web::*;
+
+use dioxus::prelude::document;
+
+/// A text file chosen by the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenedFile {
+    /// File name, without the directory
+    pub name: String,
+    pub text: String,
+}
+
+impl OpenedFile {
+    /// Lower-case extension of the file name
+    pub fn extension(&self) -> String {
+        self.name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+/// Copies `text` to the clipboard; false if the clipboard is not available, as on
+/// pages that are not served over https
+pub async fn copy_to_clipboard(text: &str) -> bool {
+    let eval = document::eval(
+        r#"
+        const text = await dioxus.recv();
+        if (!navigator.clipboard) {
+            return false;
+        }
+        try {
+            await navigator.clipboard.writeText(text);
+            return true;
+        } catch {
+            return false;
+        }
+        "#,
+    );
+    if eval.send(text).is_err() {
+        return false;
+    }
+    eval.join::<bool>().await.unwrap_or(false)
+}
+
+/// Selects the text of the input element with the given id
+pub fn select_input_text(id: &str) {
+    document::eval(&format!("document.getElementById({id:?})?.select();"));
+}
+
+/// Date and time of a timestamp in milliseconds since the epoch, as `2024-03-01 14:05 UTC`
+#[cfg(not(target_arch = "wasm32"))]
+fn format_utc(time: f64) -> String {
+    let minutes = (time / 60_000.0).floor() as i64;
+    let days = minutes.div_euclid(24 * 60);
+    let minute_of_day = minutes.rem_euclid(24 * 60);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+        minute_of_day / 60,
+        minute_of_day % 60
+    )
+}
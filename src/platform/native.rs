@@ -0,0 +1,151 @@
+//! Desktop backend: file dialogs and files in the user's data directory
+
+use super::OpenedFile;
+use dioxus::prelude::spawn;
+use rfd::AsyncFileDialog;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Shared links open the web version of the app
+const APP_ADDRESS: &str = "https://bunchofcellulose.github.io/celestialsphere/";
+const RECENT_FILES_KEY: &str = "recent-files";
+const MAX_RECENT_FILES: usize = 8;
+/// Saved diagrams, which are remembered as recent files
+const DIAGRAM_EXTENSION: &str = "json";
+
+/// Milliseconds since the epoch
+pub fn now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
+pub async fn sleep_ms(ms: u32) {
+    tokio::time::sleep(std::time::Duration::from_millis(ms.into())).await;
+}
+
+pub fn log_error(message: &str) {
+    eprintln!("{message}");
+}
+
+/// Date and time of a timestamp in milliseconds since the epoch, as `2024-03-01 14:05 UTC`
+pub fn format_time(time: f64) -> String {
+    super::format_utc(time)
+}
+
+/// Directory of the app's own files, created on first use
+fn data_dir() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("celestialsphere");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn storage_path(key: &str) -> Option<PathBuf> {
+    Some(data_dir()?.join(format!("{key}.json")))
+}
+
+pub fn storage_get(key: &str) -> Option<String> {
+    std::fs::read_to_string(storage_path(key)?).ok()
+}
+
+pub fn storage_set(key: &str, value: &str) -> Result<(), String> {
+    let path = storage_path(key).ok_or("The data directory is not available")?;
+    // Written next to the old file first, so that a crash never leaves half a file
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, value).map_err(|e| e.to_string())?;
+    std::fs::rename(&temporary, &path).map_err(|e| e.to_string())
+}
+
+pub fn storage_remove(key: &str) {
+    if let Some(path) = storage_path(key) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Paths of the diagrams opened or saved recently, newest first
+pub fn recent_files() -> Vec<String> {
+    storage_get(RECENT_FILES_KEY)
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|path| Path::new(path).is_file())
+        .collect()
+}
+
+fn add_recent_file(path: &Path) {
+    let is_diagram = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case(DIAGRAM_EXTENSION));
+    let Some(path) = path.to_str().filter(|_| is_diagram) else {
+        return;
+    };
+    let mut recent = recent_files();
+    recent.retain(|recent| recent != path);
+    recent.insert(0, path.to_string());
+    recent.truncate(MAX_RECENT_FILES);
+    if let Ok(json) = serde_json::to_string(&recent) {
+        if let Err(error) = storage_set(RECENT_FILES_KEY, &json) {
+            log_error(&format!("Cannot remember recent files: {error}"));
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<OpenedFile, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|error| format!("Cannot read {}: {error}", path.display()))?;
+    add_recent_file(path);
+    Ok(OpenedFile {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        text,
+    })
+}
+
+/// Asks where to save `content`, suggesting `filename`
+pub fn save_file(content: &[u8], filename: &str) {
+    let content = content.to_vec();
+    let mut dialog = AsyncFileDialog::new().set_file_name(filename);
+    if let Some((_, extension)) = filename.rsplit_once('.') {
+        dialog = dialog.add_filter(extension.to_uppercase(), &[extension]);
+    }
+    spawn(async move {
+        let Some(file) = dialog.save_file().await else {
+            return;
+        };
+        match std::fs::write(file.path(), &content) {
+            Ok(()) => add_recent_file(file.path()),
+            Err(error) => log_error(&format!("Cannot save {}: {error}", file.path().display())),
+        }
+    });
+}
+
+/// Lets the user choose a file with one of the given extensions and reads it as text.
+/// `on_open` is not called if the user cancels.
+pub fn open_file(extensions: &[&str], on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {
+    let dialog = AsyncFileDialog::new().add_filter("Diagrams and points", extensions);
+    spawn(async move {
+        if let Some(file) = dialog.pick_file().await {
+            on_open(read_file(file.path()));
+        }
+    });
+}
+
+/// Opens one of the `recent_files`
+pub fn open_recent(path: &str, on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {
+    on_open(read_file(Path::new(path)));
+}
+
+/// Address of the web version of the app, which shared links open
+pub fn app_address() -> Result<String, String> {
+    Ok(APP_ADDRESS.to_string())
+}
+
+/// The desktop app is not opened from an address
+pub fn location_fragment() -> Option<String> {
+    None
+}
+
+pub fn clear_location_fragment() {}
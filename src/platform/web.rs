@@ -0,0 +1,144 @@
+//! Browser backend: downloads, file inputs and local storage
+
+use super::OpenedFile;
+use gloo_timers::future::TimeoutFuture;
+use web_sys::{
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    window, HtmlElement, HtmlInputElement, Url,
+};
+
+/// Milliseconds since the epoch
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+pub async fn sleep_ms(ms: u32) {
+    TimeoutFuture::new(ms).await;
+}
+
+pub fn log_error(message: &str) {
+    web_sys::console::error_1(&message.into());
+}
+
+/// Date and time of a timestamp, in the browser's locale
+pub fn format_time(time: f64) -> String {
+    js_sys::Date::new(&time.into())
+        .to_locale_string("default", &js_sys::Object::new())
+        .into()
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    window()?.local_storage().ok().flatten()
+}
+
+pub fn storage_get(key: &str) -> Option<String> {
+    storage()?.get_item(key).ok().flatten()
+}
+
+pub fn storage_set(key: &str, value: &str) -> Result<(), String> {
+    let storage = storage().ok_or("Local storage is not available")?;
+    storage
+        .set_item(key, value)
+        .map_err(|_| "Local storage is full".to_string())
+}
+
+pub fn storage_remove(key: &str) {
+    if let Some(storage) = storage() {
+        let _ = storage.remove_item(key);
+    }
+}
+
+/// Offers `content` as a download named `filename`
+pub fn save_file(content: &[u8], filename: &str) {
+    let array = js_sys::Uint8Array::from(content);
+    let Ok(blob) = web_sys::Blob::new_with_u8_array_sequence(&js_sys::Array::of1(&array)) else {
+        return;
+    };
+    let Ok(url) = Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+    let document = window().unwrap().document().unwrap();
+    if let Ok(a) = document.create_element("a") {
+        if a.set_attribute("href", &url).is_ok() && a.set_attribute("download", filename).is_ok() {
+            a.dyn_ref::<HtmlElement>().unwrap().click();
+        }
+    }
+    let _ = Url::revoke_object_url(&url);
+}
+
+/// Lets the user choose a file with one of the given extensions and reads it as text.
+/// `on_open` is not called if the user cancels.
+pub fn open_file(extensions: &[&str], on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {
+    let document = window().unwrap().document().unwrap();
+    let Some(input) = document
+        .create_element("input")
+        .ok()
+        .and_then(|element| element.dyn_into::<HtmlInputElement>().ok())
+    else {
+        return;
+    };
+    input.set_type("file");
+    let accept: Vec<String> = extensions.iter().map(|e| format!(".{e}")).collect();
+    input.set_accept(&accept.join(","));
+
+    let chosen = input.clone();
+    let mut on_open = Some(on_open);
+    let onchange = Closure::wrap(Box::new(move |_: web_sys::Event| {
+        let Some(file) = chosen.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        let Some(on_open) = on_open.take() else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+            on_open(
+                text.ok()
+                    .and_then(|text| text.as_string())
+                    .map(|text| OpenedFile {
+                        name: file.name(),
+                        text,
+                    })
+                    .ok_or_else(|| "The file cannot be read".to_string()),
+            );
+        });
+    }) as Box<dyn FnMut(_)>);
+    input.set_onchange(Some(onchange.as_ref().unchecked_ref()));
+    onchange.forget();
+    input.click();
+}
+
+/// Files opened or saved recently; the browser does not give them back
+pub fn recent_files() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn open_recent(_path: &str, _on_open: impl FnOnce(Result<OpenedFile, String>) + 'static) {}
+
+/// Address of the page, without the fragment
+pub fn app_address() -> Result<String, String> {
+    let href = window()
+        .ok_or("No browser window")?
+        .location()
+        .href()
+        .map_err(|_| "Cannot read the page address")?;
+    Ok(href.split('#').next().unwrap_or_default().to_string())
+}
+
+/// Fragment of the address the page was opened with, including the `#`
+pub fn location_fragment() -> Option<String> {
+    window()?.location().hash().ok()
+}
+
+/// Removes the fragment from the address without reloading the page
+pub fn clear_location_fragment() {
+    let Some(window) = window() else {
+        return;
+    };
+    if let Ok(history) = window.history() {
+        let path = window.location().pathname().unwrap_or_default();
+        let search = window.location().search().unwrap_or_default();
+        let _ =
+            history.replace_state_with_url(&JsValue::NULL, "", Some(&format!("{path}{search}")));
+    }
+}
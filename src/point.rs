@@ -1,4 +1,7 @@
-use web_sys::window;
+use crate::construction::Construction;
+use crate::projection::Projection;
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 
 pub type Vec3 = [f64; 3];
 
@@ -12,7 +15,10 @@ pub struct Point {
     pub name: String,
     pub movable: bool,
     pub removable: bool,
-    pub hidden: bool
+    pub hidden: bool,
+    pub construction: Option<Construction>,
+    /// Fill color chosen from the context menu, as `#rrggbb`
+    pub color: Option<String>,
 }
 
 impl Point {
@@ -25,6 +31,8 @@ impl Point {
             movable: true,
             removable: true,
             hidden: false,
+            construction: None,
+            color: None,
             abs_polar: vec3_to_polar(vec),
             rot_polar: vec3_to_polar(vec),
         }
@@ -40,6 +48,8 @@ impl Point {
             movable: true,
             removable: true,
             hidden: false,
+            construction: None,
+            color: None,
             abs_polar: vec3_to_polar(vec),
             rot_polar: vec3_to_polar(rotated),
         }
@@ -55,6 +65,8 @@ impl Point {
             movable: true,
             removable: true,
             hidden: false,
+            construction: None,
+            color: None,
             abs_polar: vec3_to_polar(absolute),
             rot_polar: vec3_to_polar(vec),
         }
@@ -70,6 +82,14 @@ impl Point {
         self.rot_polar = vec3_to_polar(vec);
     }
 
+    /// Places the point at an absolute position, regardless of whether it is movable
+    pub fn set_absolute(&mut self, vec: Vec3, q: Quaternion) {
+        self.absolute = vec;
+        self.abs_polar = vec3_to_polar(vec);
+        self.rotated = q.rotate_point_active(vec);
+        self.rot_polar = vec3_to_polar(self.rotated);
+    }
+
     pub fn rotate(&mut self, q: Quaternion) {
         self.rotated = q.rotate_point_active(self.absolute);
         self.rot_polar = vec3_to_polar(self.rotated);
@@ -90,11 +110,21 @@ impl Point {
             movable: true,
             removable: true,
             hidden: false,
+            construction: Some(Construction::Antipode(self.id)),
+            color: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Name of a point for titles and descriptions, falling back to its index
+pub fn point_label(points: &[Point], id: usize) -> String {
+    match points.get(id).map(|point| point.name.as_str()) {
+        Some("") | None => format!("#{id}"),
+        Some(name) => name.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Quaternion {
     w: f64,
     x: f64,
@@ -103,6 +133,10 @@ pub struct Quaternion {
 }
 
 impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Quaternion { w, x, y, z }
+    }
+
     pub fn from_euler_deg(euler: Vec3) -> Self {
         let [yaw, pitch, roll] = euler.map(|x| x.to_radians());
         let cr = (roll / 2.0).cos();
@@ -188,6 +222,102 @@ impl Quaternion {
         [rotated.x, rotated.y, rotated.z]
     }
 
+    /// Shortest rotation taking the direction `from` onto the direction `to`
+    pub fn from_two_vectors(from: Vec3, to: Vec3) -> Self {
+        let dot = (from[0] * to[0] + from[1] * to[1] + from[2] * to[2]).clamp(-1.0, 1.0);
+        let axis = [
+            from[1] * to[2] - from[2] * to[1],
+            from[2] * to[0] - from[0] * to[2],
+            from[0] * to[1] - from[1] * to[0],
+        ];
+        let axis_norm = (axis[0].powi(2) + axis[1].powi(2) + axis[2].powi(2)).sqrt();
+        if axis_norm < 1e-10 {
+            if dot > 0.0 {
+                return Self::identity();
+            }
+            // Antiparallel: any axis perpendicular to `from` will do
+            let perp = if from[0].abs() < 0.9 {
+                [0.0, from[2], -from[1]]
+            } else {
+                [-from[2], 0.0, from[0]]
+            };
+            return Self::from_axis_angle(perp, std::f64::consts::PI);
+        }
+        Self::from_axis_angle(axis, dot.acos())
+    }
+
+    pub fn is_finite(self) -> bool {
+        [self.w, self.x, self.y, self.z]
+            .iter()
+            .all(|c| c.is_finite())
+    }
+
+    pub fn normalize(self) -> Self {
+        let norm = (self.w.powi(2) + self.x.powi(2) + self.y.powi(2) + self.z.powi(2)).sqrt();
+        if norm < 1e-10 {
+            return Self::identity();
+        }
+        Quaternion {
+            w: self.w / norm,
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+        }
+    }
+
+    /// Rotation axis and angle (radians) of a unit quaternion
+    pub fn to_axis_angle(self) -> (Vec3, f64) {
+        let q = if self.w < 0.0 {
+            Quaternion {
+                w: -self.w,
+                x: -self.x,
+                y: -self.y,
+                z: -self.z,
+            }
+        } else {
+            self
+        };
+        let sin_half = (q.x.powi(2) + q.y.powi(2) + q.z.powi(2)).sqrt();
+        if sin_half < 1e-12 {
+            return ([0.0, 0.0, 1.0], 0.0);
+        }
+        let angle = 2.0 * sin_half.atan2(q.w);
+        ([q.x / sin_half, q.y / sin_half, q.z / sin_half], angle)
+    }
+
+    /// Spherical linear interpolation between two orientations, `t` in [0, 1]
+    pub fn slerp(self, other: Quaternion, t: f64) -> Self {
+        let mut other = other;
+        let mut dot = self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z;
+        // Take the short way around
+        if dot < 0.0 {
+            other = Quaternion {
+                w: -other.w,
+                x: -other.x,
+                y: -other.y,
+                z: -other.z,
+            };
+            dot = -dot;
+        }
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = dot.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+        Quaternion {
+            w: a * self.w + b * other.w,
+            x: a * self.x + b * other.x,
+            y: a * self.y + b * other.y,
+            z: a * self.z + b * other.z,
+        }
+        .normalize()
+    }
+
     pub fn to_euler_deg(self) -> Vec3 {
         let Quaternion { w, x, y, z } = self;
         let mut yaw = f64::atan2(2.0 * (w * z + x * y), 1.0 - 2.0 * (y * y + z * z)).to_degrees();
@@ -200,32 +330,95 @@ impl Quaternion {
     }
 }
 
-pub fn transform_viewport_to_sphere(viewport_x: f64, viewport_y: f64) -> Vec3 {
-    let nan = [f64::NAN; 3];
-    let Some(window) = window() else {
-        return nan;
-    };
-    let Some(document) = window.document() else {
-        return nan;
-    };
-    let Some(Some(circle_element)) = document.query_selector("circle").ok() else {
-        return nan;
+/// Where the sphere's drawing is on the screen, kept up to date by the `Sphere`
+/// component so that pointer positions can be mapped without querying the page
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SphereFrame {
+    /// Bounding box of the drawing, in viewport pixels
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+    zoom: f64,
+}
+
+thread_local! {
+    static SPHERE_FRAME: Cell<SphereFrame> = const {
+        Cell::new(SphereFrame {
+            left: 0.0,
+            top: 0.0,
+            width: 0.0,
+            height: 0.0,
+            zoom: 1.0,
+        })
     };
+}
+
+/// Records the bounding box of the sphere's drawing, in viewport pixels
+pub fn set_sphere_bounds(left: f64, top: f64, width: f64, height: f64) {
+    SPHERE_FRAME.with(|frame| {
+        frame.set(SphereFrame {
+            left,
+            top,
+            width,
+            height,
+            ..frame.get()
+        })
+    });
+}
 
-    let rect = circle_element.get_bounding_client_rect();
+pub fn set_sphere_zoom(zoom: f64) {
+    SPHERE_FRAME.with(|frame| {
+        frame.set(SphereFrame {
+            zoom,
+            ..frame.get()
+        })
+    });
+}
 
-    let (circle_left, circle_top, circle_width, circle_height) =
-        (rect.left(), rect.top(), rect.width(), rect.height());
+/// Maps viewport coordinates to view coordinates, in units of the sphere radius
+/// around the view center
+pub fn transform_viewport_to_disc(viewport_x: f64, viewport_y: f64) -> Option<(f64, f64)> {
+    let frame = SPHERE_FRAME.with(Cell::get);
+    // The view box is 100 / zoom units wide, centered on the drawing; the sphere's
+    // radius is 25 units
+    let radius = frame.width.min(frame.height) * frame.zoom / 4.0;
+    if radius <= 0.0 {
+        return None;
+    }
 
-    let circle_x = (viewport_x - circle_left - circle_width / 2.0) / circle_width * 2.0;
-    let circle_y = (viewport_y - circle_top - circle_height / 2.0) / circle_height * 2.0;
+    let circle_x = (viewport_x - frame.left - frame.width / 2.0) / radius;
+    let circle_y = (viewport_y - frame.top - frame.height / 2.0) / radius;
+
+    Some((circle_x, circle_y))
+}
+
+/// Point of the sphere, in the rotated frame, drawn at the given viewport
+/// position; NaN when there is none
+pub fn transform_viewport_to_sphere(
+    viewport_x: f64,
+    viewport_y: f64,
+    projection: Projection,
+) -> Vec3 {
+    transform_viewport_to_disc(viewport_x, viewport_y)
+        .and_then(|(circle_x, circle_y)| projection.unproject(circle_x, circle_y))
+        .unwrap_or([f64::NAN; 3])
+}
+
+/// Arcball mapping: like `transform_viewport_to_sphere`, but positions outside
+/// the sphere are pulled onto its limb so that dragging never loses grip
+pub fn transform_viewport_to_arcball(viewport_x: f64, viewport_y: f64) -> Vec3 {
+    let Some((circle_x, circle_y)) = transform_viewport_to_disc(viewport_x, viewport_y) else {
+        return [f64::NAN; 3];
+    };
 
     let r2 = circle_x.powi(2) + circle_y.powi(2);
     if r2 <= 1.0 {
-        return [circle_x, circle_y, (1.0 - r2).sqrt()];
+        [circle_x, circle_y, (1.0 - r2).sqrt()]
+    } else {
+        let r = r2.sqrt();
+        [circle_x / r, circle_y / r, 0.0]
     }
-
-    nan
 }
 
 pub fn arc_distance(a: Vec3, b: Vec3) -> f64 {
@@ -234,6 +427,25 @@ pub fn arc_distance(a: Vec3, b: Vec3) -> f64 {
     dot.acos()
 }
 
+/// Sides a, b, c and angles A, B, C of the triangle with vertices `a`, `b`, `c`,
+/// followed by its spherical excess, all in degrees
+pub fn spherical_triangle(a: Vec3, b: Vec3, c: Vec3) -> [f64; 7] {
+    let side_a = arc_distance(b, c);
+    let side_b = arc_distance(a, c);
+    let side_c = arc_distance(a, b);
+    let [angle_a, angle_b, angle_c] = calculate_angle(side_a, side_b, side_c);
+    let [aa, ab, ac] = [angle_a, angle_b, angle_c].map(f64::to_degrees);
+    [
+        side_a.to_degrees(),
+        side_b.to_degrees(),
+        side_c.to_degrees(),
+        aa,
+        ab,
+        ac,
+        aa + ab + ac - 180.0,
+    ]
+}
+
 pub fn calculate_angle(a: f64, b: f64, c: f64) -> [f64; 3] {
     let cos_a = a.cos();
     let cos_b = b.cos();
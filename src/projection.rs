@@ -0,0 +1,395 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, PI, SQRT_2};
+
+/// Half-width of the world maps, in sphere radii
+const MAP_EXTENT: f64 = 1.9;
+/// Consecutive projected vertices further apart than this (in sphere radii) lie
+/// on opposite sides of a map seam and are not joined
+const SEAM_JUMP: f64 = 1.0;
+/// Gnomonic maps only show points at least this far in front of the limb
+const GNOMONIC_MIN_Z: f64 = 0.1;
+
+/// Unbroken pieces of a projected polyline, in view coordinates
+pub type Runs = Vec<Vec<[f64; 2]>>;
+
+/// Map projection used to draw the sphere.
+///
+/// Projections work in the rotated frame, looking down the +z axis, and map to
+/// view coordinates in units of the sphere radius with the view center at the
+/// origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Projection {
+    #[default]
+    Orthographic,
+    Stereographic,
+    Gnomonic,
+    LambertAzimuthal,
+    Equirectangular,
+    Mollweide,
+    Aitoff,
+}
+
+impl Projection {
+    pub const ALL: [Projection; 7] = [
+        Projection::Orthographic,
+        Projection::Stereographic,
+        Projection::Gnomonic,
+        Projection::LambertAzimuthal,
+        Projection::Equirectangular,
+        Projection::Mollweide,
+        Projection::Aitoff,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Projection::Orthographic => "Orthographic",
+            Projection::Stereographic => "Stereographic",
+            Projection::Gnomonic => "Gnomonic",
+            Projection::LambertAzimuthal => "Lambert azimuthal equal-area",
+            Projection::Equirectangular => "Equirectangular",
+            Projection::Mollweide => "Mollweide",
+            Projection::Aitoff => "Aitoff",
+        }
+    }
+
+    fn is_azimuthal(self) -> bool {
+        matches!(
+            self,
+            Projection::Orthographic
+                | Projection::Stereographic
+                | Projection::Gnomonic
+                | Projection::LambertAzimuthal
+        )
+    }
+
+    /// Whether a point is drawn in full rather than as a faded back-side point.
+    /// Azimuthal projections fade the far hemisphere, world maps show everything.
+    pub fn is_front(self, v: Vec3) -> bool {
+        !self.is_azimuthal() || v[2] >= self.front_z()
+    }
+
+    /// Whether the map tears the sphere apart somewhere: along the back meridian
+    /// for the world maps, around the antipode of the view center for Lambert
+    fn has_seam(self) -> bool {
+        !matches!(
+            self,
+            Projection::Orthographic | Projection::Stereographic | Projection::Gnomonic
+        )
+    }
+
+    /// Depth at which azimuthal projections switch from front to back
+    fn front_z(self) -> f64 {
+        if self == Projection::Gnomonic {
+            GNOMONIC_MIN_Z
+        } else {
+            0.0
+        }
+    }
+
+    /// Projects a point in the rotated frame, or `None` if it has no image
+    pub fn project(self, v: Vec3) -> Option<[f64; 2]> {
+        let [x, y, z] = v;
+        match self {
+            Projection::Orthographic => Some([x, y]),
+            Projection::Stereographic | Projection::Gnomonic | Projection::LambertAzimuthal => {
+                let c = z.clamp(-1.0, 1.0).acos();
+                let rho = match self {
+                    Projection::Stereographic if c < PI - 0.02 => 2.0 * (c / 2.0).tan(),
+                    Projection::Gnomonic if z >= GNOMONIC_MIN_Z => c.tan(),
+                    Projection::LambertAzimuthal if c < PI - 1e-6 => 2.0 * (c / 2.0).sin(),
+                    _ => return None,
+                };
+                let r = (x * x + y * y).sqrt();
+                if r < 1e-12 {
+                    return Some([0.0, 0.0]);
+                }
+                Some([x / r * rho, y / r * rho])
+            }
+            Projection::Equirectangular | Projection::Mollweide | Projection::Aitoff => {
+                let lon = x.atan2(z);
+                let lat = y.clamp(-1.0, 1.0).asin();
+                let [mx, my] = match self {
+                    Projection::Equirectangular => [lon, lat],
+                    Projection::Mollweide => mollweide_forward(lon, lat),
+                    _ => aitoff_forward(lon, lat),
+                };
+                let scale = self.map_scale();
+                Some([mx * scale, my * scale])
+            }
+        }
+    }
+
+    /// Inverse of `project` for the visible part of the map
+    pub fn unproject(self, px: f64, py: f64) -> Option<Vec3> {
+        match self {
+            Projection::Orthographic => {
+                let r2 = px * px + py * py;
+                (r2 <= 1.0).then(|| [px, py, (1.0 - r2).sqrt()])
+            }
+            Projection::Stereographic | Projection::Gnomonic | Projection::LambertAzimuthal => {
+                let rho = (px * px + py * py).sqrt();
+                let c = match self {
+                    Projection::Stereographic => 2.0 * (rho / 2.0).atan(),
+                    Projection::Gnomonic => rho.atan(),
+                    _ if rho <= 2.0 => 2.0 * (rho / 2.0).asin(),
+                    _ => return None,
+                };
+                let v = if rho < 1e-12 {
+                    [0.0, 0.0, 1.0]
+                } else {
+                    [px / rho * c.sin(), py / rho * c.sin(), c.cos()]
+                };
+                // Only the gnomonic map has points without an image
+                (self != Projection::Gnomonic || self.is_front(v)).then_some(v)
+            }
+            Projection::Equirectangular | Projection::Mollweide | Projection::Aitoff => {
+                let scale = self.map_scale();
+                let (mx, my) = (px / scale, py / scale);
+                let [lon, lat] = match self {
+                    Projection::Equirectangular => [mx, my],
+                    Projection::Mollweide => mollweide_inverse(mx, my)?,
+                    _ => aitoff_inverse(mx, my)?,
+                };
+                if lon.abs() > PI || lat.abs() > FRAC_PI_2 {
+                    return None;
+                }
+                Some([lat.cos() * lon.sin(), lat.sin(), lat.cos() * lon.cos()])
+            }
+        }
+    }
+
+    /// Scale from the standard unit-sphere formulas of the world maps to the view
+    fn map_scale(self) -> f64 {
+        match self {
+            Projection::Mollweide => MAP_EXTENT / (2.0 * SQRT_2),
+            _ => MAP_EXTENT / PI,
+        }
+    }
+
+    /// Half-width of the part of the map worth showing, in sphere radii
+    pub fn extent(self) -> f64 {
+        if self == Projection::Orthographic {
+            1.0
+        } else {
+            2.0f64.min(
+                self.outline()
+                    .into_iter()
+                    .flatten()
+                    .fold(0.0, |m: f64, [x, y]| m.max(x.abs()).max(y.abs())),
+            )
+        }
+    }
+
+    /// Boundary of the map as a closed polyline in view coordinates, `None` for
+    /// the orthographic projection whose boundary is the sphere's own limb
+    pub fn outline(self) -> Option<Vec<[f64; 2]>> {
+        let steps = 180;
+        let ellipse = |a: f64, b: f64| {
+            (0..=steps)
+                .map(|i| {
+                    let t = i as f64 * std::f64::consts::TAU / steps as f64;
+                    [a * t.cos(), b * t.sin()]
+                })
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Projection::Orthographic => None,
+            // The hemisphere boundary, which is a circle of radius 2
+            Projection::Stereographic => Some(ellipse(2.0, 2.0)),
+            // The limit of what is shown
+            Projection::Gnomonic => {
+                let rho = GNOMONIC_MIN_Z.acos().tan();
+                Some(ellipse(rho, rho))
+            }
+            Projection::LambertAzimuthal => Some(ellipse(2.0, 2.0)),
+            Projection::Equirectangular => {
+                let (w, h) = (MAP_EXTENT, MAP_EXTENT / 2.0);
+                Some(vec![[-w, -h], [w, -h], [w, h], [-w, h], [-w, -h]])
+            }
+            Projection::Mollweide | Projection::Aitoff => {
+                Some(ellipse(MAP_EXTENT, MAP_EXTENT / 2.0))
+            }
+        }
+    }
+
+    /// Splits a polyline in the rotated frame into projected front and back
+    /// parts, in view coordinates. Each part is a list of unbroken runs.
+    pub fn project_polyline(self, points: &[Vec3]) -> (Runs, Runs) {
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        let mut run: Vec<[f64; 2]> = Vec::new();
+        let mut run_front = true;
+        let mut prev: Option<Vec3> = None;
+
+        let mut finish = |run: &mut Vec<[f64; 2]>, is_front: bool| {
+            if run.len() > 1 {
+                if is_front {
+                    front.push(std::mem::take(run));
+                } else {
+                    back.push(std::mem::take(run));
+                }
+            }
+            run.clear();
+        };
+
+        for &p in points {
+            let is_front = self.is_front(p);
+            if let Some(a) = prev {
+                if self.is_front(a) != is_front {
+                    // Close the run at the limb and start the next one there
+                    let t = (a[2] - self.front_z()) / (a[2] - p[2]);
+                    let limb = [
+                        a[0] + t * (p[0] - a[0]),
+                        a[1] + t * (p[1] - a[1]),
+                        a[2] + t * (p[2] - a[2]),
+                    ];
+                    let len = (limb[0].powi(2) + limb[1].powi(2) + limb[2].powi(2)).sqrt();
+                    let crossing = if len > 1e-12 {
+                        self.project([limb[0] / len, limb[1] / len, limb[2] / len])
+                    } else {
+                        None
+                    };
+                    if let Some(c) = crossing {
+                        run.push(c);
+                    }
+                    finish(&mut run, run_front);
+                    if let Some(c) = crossing {
+                        run.push(c);
+                    }
+                }
+            }
+            run_front = is_front;
+            prev = Some(p);
+
+            let Some(q) = self.project(p) else {
+                finish(&mut run, run_front);
+                continue;
+            };
+            if let Some(last) = run.last().filter(|_| self.has_seam()) {
+                if (q[0] - last[0]).hypot(q[1] - last[1]) > SEAM_JUMP {
+                    finish(&mut run, run_front);
+                }
+            }
+            run.push(q);
+        }
+        finish(&mut run, run_front);
+
+        (front, back)
+    }
+
+    /// SVG path data for the front and back parts of a rotated polyline in the live view
+    pub fn view_paths(self, points: &[Vec3]) -> (String, String) {
+        let (front, back) = self.project_polyline(points);
+        (
+            runs_to_path(&front, (50.0, 50.0), 25.0),
+            runs_to_path(&back, (50.0, 50.0), 25.0),
+        )
+    }
+
+    /// Position of a rotated point in live view SVG units
+    pub fn view_position(self, v: Vec3) -> Option<(f64, f64)> {
+        self.project(v)
+            .map(|[x, y]| (x * 25.0 + 50.0, y * 25.0 + 50.0))
+    }
+}
+
+/// Joins runs of view coordinates into SVG path data, scaled by `scale` around `center`
+pub fn runs_to_path(runs: &[Vec<[f64; 2]>], center: (f64, f64), scale: f64) -> String {
+    runs.iter()
+        .map(|run| {
+            run.iter()
+                .enumerate()
+                .map(|(i, [x, y])| {
+                    let command = if i == 0 { "M" } else { "L" };
+                    format!(
+                        "{command} {:.3} {:.3}",
+                        center.0 + x * scale,
+                        center.1 + y * scale
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn mollweide_forward(lon: f64, lat: f64) -> [f64; 2] {
+    // Solve 2θ + sin 2θ = π sin φ for the auxiliary angle θ
+    let target = PI * lat.sin();
+    let mut theta = lat;
+    if lat.abs() < FRAC_PI_2 - 1e-9 {
+        for _ in 0..20 {
+            let f = 2.0 * theta + (2.0 * theta).sin() - target;
+            let df = 2.0 + 2.0 * (2.0 * theta).cos();
+            if df.abs() < 1e-12 {
+                break;
+            }
+            let step = f / df;
+            theta -= step;
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+    }
+    [2.0 * SQRT_2 / PI * lon * theta.cos(), SQRT_2 * theta.sin()]
+}
+
+fn mollweide_inverse(x: f64, y: f64) -> Option<[f64; 2]> {
+    let s = y / SQRT_2;
+    if s.abs() > 1.0 {
+        return None;
+    }
+    let theta = s.asin();
+    let lat = ((2.0 * theta + (2.0 * theta).sin()) / PI)
+        .clamp(-1.0, 1.0)
+        .asin();
+    let cos_theta = theta.cos();
+    let lon = if cos_theta < 1e-12 {
+        0.0
+    } else {
+        PI * x / (2.0 * SQRT_2 * cos_theta)
+    };
+    Some([lon, lat])
+}
+
+fn aitoff_forward(lon: f64, lat: f64) -> [f64; 2] {
+    let alpha = (lat.cos() * (lon / 2.0).cos()).clamp(-1.0, 1.0).acos();
+    let sinc = if alpha < 1e-12 {
+        1.0
+    } else {
+        alpha.sin() / alpha
+    };
+    [2.0 * lat.cos() * (lon / 2.0).sin() / sinc, lat.sin() / sinc]
+}
+
+fn aitoff_inverse(x: f64, y: f64) -> Option<[f64; 2]> {
+    // The map is the ellipse with semi-axes π and π/2
+    if (x / PI).powi(2) + (y / FRAC_PI_2).powi(2) > 1.0 {
+        return None;
+    }
+    // No closed form: Newton's method from the equirectangular guess
+    let (mut lon, mut lat) = (x, y);
+    let h = 1e-7;
+    for _ in 0..30 {
+        let [fx, fy] = aitoff_forward(lon, lat);
+        let (ex, ey) = (fx - x, fy - y);
+        if ex.abs() < 1e-10 && ey.abs() < 1e-10 {
+            break;
+        }
+        let [ax, ay] = aitoff_forward(lon + h, lat);
+        let [bx, by] = aitoff_forward(lon, lat + h);
+        let (j11, j21) = ((ax - fx) / h, (ay - fy) / h);
+        let (j12, j22) = ((bx - fx) / h, (by - fy) / h);
+        let det = j11 * j22 - j12 * j21;
+        if det.abs() < 1e-14 {
+            break;
+        }
+        lon -= (j22 * ex - j12 * ey) / det;
+        lat -= (j11 * ey - j21 * ex) / det;
+        lon = lon.clamp(-PI, PI);
+        lat = lat.clamp(-FRAC_PI_2, FRAC_PI_2);
+    }
+    Some([lon, lat])
+}
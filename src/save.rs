@@ -0,0 +1,977 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version written by `SaveFile::to_json`; bump it and add a migration step
+/// in `migrate` whenever the format changes incompatibly
+pub const SAVE_VERSION: u64 = 1;
+
+/// Contents of a saved diagram (.json file)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u64,
+    pub points: Vec<SavedPoint>,
+    #[serde(default)]
+    pub arcs: Vec<SavedArc>,
+    #[serde(default)]
+    pub great_circles: Vec<SavedGreatCircle>,
+    #[serde(default)]
+    pub small_circles: Vec<SavedSmallCircle>,
+    #[serde(default)]
+    pub constraints: Vec<Constraint>,
+    #[serde(default)]
+    pub traces: Vec<Trace>,
+    /// Point indices of each group
+    #[serde(default)]
+    pub groups: Vec<Vec<usize>>,
+    #[serde(default)]
+    pub view: SavedView,
+    #[serde(default)]
+    pub display: SavedDisplay,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedPoint {
+    /// Position on the unit sphere, before the view rotation
+    pub position: Vec3,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub movable: bool,
+    #[serde(default = "default_true")]
+    pub removable: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    /// Definition of a derived point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub construction: Option<Construction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// Arc of a great circle between two points, given by their indices
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedArc {
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedGreatCircle {
+    pub pole: usize,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedSmallCircle {
+    pub pole: usize,
+    pub plane_distance: f64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub through: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+/// Orientation, zoom and projection the diagram was looked at with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedView {
+    pub quaternion: Quaternion,
+    pub zoom: f64,
+    pub projection: Projection,
+}
+
+impl Default for SavedView {
+    fn default() -> Self {
+        Self {
+            quaternion: Quaternion::identity(),
+            zoom: 1.0,
+            projection: Projection::default(),
+        }
+    }
+}
+
+/// Display toggles of the top left panel
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedDisplay {
+    pub show_grid: bool,
+    pub grid: GridConfig,
+    pub show_hidden: bool,
+    pub show_center: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Deviation from unit length that loading silently accepts
+const UNIT_TOLERANCE: f64 = 1e-6;
+
+/// Unit vector in the direction of `v`, or `None` if it has no usable direction
+fn unit(v: Vec3) -> Option<Vec3> {
+    let length = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    (length.is_finite() && length > 1e-9).then(|| v.map(|c| c / length))
+}
+
+/// Whether `color` is a `#rrggbb` color, as written by color inputs
+fn is_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_unit(v: Vec3) -> bool {
+    ((v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt() - 1.0).abs() <= UNIT_TOLERANCE
+}
+
+/// Unversioned format of the first releases, with positional tuples
+#[derive(Deserialize)]
+struct LegacySaveData {
+    points: Vec<(Vec3, String, bool, bool)>,
+    arcs: Vec<(usize, usize)>,
+    great_circles: Vec<(usize, String)>,
+    small_circles: Vec<(usize, f64, String)>,
+    /// Definitions of derived points, parallel to `points`
+    #[serde(default)]
+    constructions: Vec<Option<Construction>>,
+    /// Points the small circles pass through, parallel to `small_circles`
+    #[serde(default)]
+    small_circle_through: Vec<Option<usize>>,
+    #[serde(default)]
+    constraints: Vec<Constraint>,
+    #[serde(default)]
+    traces: Vec<Trace>,
+}
+
+impl From<LegacySaveData> for SaveFile {
+    fn from(data: LegacySaveData) -> Self {
+        SaveFile {
+            version: 1,
+            points: data
+                .points
+                .into_iter()
+                .enumerate()
+                .map(|(i, (position, name, movable, removable))| SavedPoint {
+                    position,
+                    name,
+                    movable,
+                    removable,
+                    hidden: false,
+                    construction: data.constructions.get(i).copied().flatten(),
+                    color: None,
+                })
+                .collect(),
+            arcs: data
+                .arcs
+                .into_iter()
+                .map(|(from, to)| SavedArc { from, to })
+                .collect(),
+            great_circles: data
+                .great_circles
+                .into_iter()
+                .map(|(pole, name)| SavedGreatCircle {
+                    pole,
+                    name,
+                    color: None,
+                })
+                .collect(),
+            small_circles: data
+                .small_circles
+                .into_iter()
+                .enumerate()
+                .map(|(i, (pole, plane_distance, name))| SavedSmallCircle {
+                    pole,
+                    plane_distance,
+                    name,
+                    through: data.small_circle_through.get(i).copied().flatten(),
+                    color: None,
+                })
+                .collect(),
+            constraints: data.constraints,
+            traces: data.traces,
+            groups: Vec::new(),
+            view: SavedView::default(),
+            display: SavedDisplay::default(),
+        }
+    }
+}
+
+/// Brings a parsed file of any known version up to `SAVE_VERSION`, one version at a time
+fn migrate(mut value: Value) -> Result<Value, String> {
+    loop {
+        let version = match value.get("version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .ok_or_else(|| format!("Invalid format version {version}"))?,
+        };
+        value = match version {
+            0 => {
+                let legacy: LegacySaveData = serde_json::from_value(value)
+                    .map_err(|e| format!("Failed to read unversioned file: {e}"))?;
+                serde_json::to_value(SaveFile::from(legacy)).map_err(|e| e.to_string())?
+            }
+            SAVE_VERSION => return Ok(value),
+            _ => {
+                return Err(format!(
+                    "The file was saved in format version {version}, which is newer than \
+                     this version of the app supports ({SAVE_VERSION})"
+                ))
+            }
+        };
+    }
+}
+
+impl SaveFile {
+    /// Describes the current diagram
+    pub fn new(
+        points: &[Point],
+        arcs: &[(usize, usize)],
+        great_circles: &[GreatCircle],
+        small_circles: &[SmallCircle],
+        state: &State,
+    ) -> Self {
+        SaveFile {
+            version: SAVE_VERSION,
+            points: points
+                .iter()
+                .map(|point| SavedPoint {
+                    position: point.absolute,
+                    name: point.name.clone(),
+                    movable: point.movable,
+                    removable: point.removable,
+                    hidden: point.hidden,
+                    construction: point.construction,
+                    color: point.color.clone(),
+                })
+                .collect(),
+            arcs: arcs
+                .iter()
+                .map(|&(from, to)| SavedArc { from, to })
+                .collect(),
+            great_circles: great_circles
+                .iter()
+                .map(|gc| SavedGreatCircle {
+                    pole: gc.pole,
+                    name: gc.name.clone(),
+                    color: gc.color.clone(),
+                })
+                .collect(),
+            small_circles: small_circles
+                .iter()
+                .map(|sc| SavedSmallCircle {
+                    pole: sc.pole,
+                    plane_distance: sc.plane_distance,
+                    name: sc.name.clone(),
+                    through: sc.through,
+                    color: sc.color.clone(),
+                })
+                .collect(),
+            constraints: state.constraints.clone(),
+            traces: state.traces.clone(),
+            groups: state.groups.clone(),
+            view: SavedView {
+                quaternion: state.quaternion,
+                zoom: state.zoom,
+                projection: state.projection,
+            },
+            display: SavedDisplay {
+                show_grid: state.show_grid,
+                grid: state.grid.clone(),
+                show_hidden: state.show_hidden,
+                show_center: state.show_center,
+            },
+        }
+    }
+
+    /// Reads a saved diagram, migrating files written by older versions
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| format!("Failed to parse JSON: {e}"))?;
+        serde_json::from_value(migrate(value)?).map_err(|e| format!("Invalid save file: {e}"))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// Points as seen from the saved view
+    pub fn points(&self) -> Vec<Point> {
+        let q = self.view.quaternion.normalize();
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(i, saved)| {
+                let mut point = Point::from_vec3_absolute(i, saved.position, q);
+                point.name = saved.name.clone();
+                point.movable = saved.movable;
+                point.removable = saved.removable;
+                point.hidden = saved.hidden;
+                point.construction = saved.construction;
+                point.color = saved.color.clone();
+                point
+            })
+            .collect()
+    }
+
+    pub fn arcs(&self) -> Vec<(usize, usize)> {
+        self.arcs.iter().map(|arc| (arc.from, arc.to)).collect()
+    }
+
+    pub fn great_circles(&self) -> Vec<GreatCircle> {
+        self.great_circles
+            .iter()
+            .map(|saved| {
+                let mut gc = GreatCircle::new(saved.pole);
+                gc.name = saved.name.clone();
+                gc.color = saved.color.clone();
+                gc
+            })
+            .collect()
+    }
+
+    pub fn small_circles(&self) -> Vec<SmallCircle> {
+        self.small_circles
+            .iter()
+            .map(|saved| {
+                let mut sc = SmallCircle::new(saved.pole, saved.plane_distance);
+                sc.name = saved.name.clone();
+                sc.through = saved.through;
+                sc.color = saved.color.clone();
+                sc
+            })
+            .collect()
+    }
+
+    /// Fresh app state showing the diagram as it was saved, with nothing selected
+    pub fn state(&self) -> State {
+        let mut state = State::initialize();
+        state.quaternion = self.view.quaternion.normalize();
+        state.rotation = state.quaternion.to_euler_deg();
+        state.zoom = self.view.zoom.clamp(0.5, 2.0);
+        state.projection = self.view.projection;
+        state.show_grid = self.display.show_grid;
+        state.grid = self.display.grid.clone();
+        state.show_hidden = self.display.show_hidden;
+        state.show_center = self.display.show_center;
+        state.groups = self.groups.clone();
+        state.constraints = self.constraints.clone();
+        state.traces = self.traces.clone();
+        state
+    }
+
+    /// Describes the saved view of the diagram, as the image exports draw it
+    pub fn scene(&self) -> Scene {
+        Scene::build(
+            &self.points(),
+            &self.arcs(),
+            &self.great_circles(),
+            &self.small_circles(),
+            &self.state(),
+        )
+    }
+
+    /// Checks that the file describes a diagram the app can show. Problems that
+    /// can be repaired without guessing are fixed, dropping the broken item if
+    /// needed, and described in the returned list; a file whose points cannot be
+    /// placed on the sphere is rejected.
+    pub fn validate(&mut self) -> Result<Vec<String>, String> {
+        let mut repairs = Vec::new();
+        let count = self.points.len();
+        let exists = |id: usize| id < count;
+
+        for (i, point) in self.points.iter_mut().enumerate() {
+            let Some(position) = unit(point.position) else {
+                return Err(format!(
+                    "Point {i} has no valid position ({:?})",
+                    point.position
+                ));
+            };
+            if !is_unit(point.position) {
+                repairs.push(format!("Point {i} was moved onto the sphere"));
+            }
+            point.position = position;
+            if let Some(construction) = point.construction {
+                if let Some(&parent) = construction
+                    .parents()
+                    .iter()
+                    .find(|&&parent| !exists(parent) || parent == i)
+                {
+                    repairs.push(format!(
+                        "Point {i} was derived from point {parent}, which is invalid; it is now an ordinary point"
+                    ));
+                    point.construction = None;
+                }
+            }
+            if point.color.as_deref().is_some_and(|color| !is_color(color)) {
+                repairs.push(format!(
+                    "Point {i} had an invalid color and is drawn in the default one"
+                ));
+                point.color = None;
+            }
+        }
+
+        let mut arcs: Vec<SavedArc> = Vec::new();
+        for (i, arc) in self.arcs.iter().enumerate() {
+            if !exists(arc.from) || !exists(arc.to) || arc.from == arc.to {
+                repairs.push(format!(
+                    "Arc {i} between points {} and {} was removed, as it does not join two existing points",
+                    arc.from, arc.to
+                ));
+            } else if arcs.iter().any(|other| {
+                (other.from, other.to) == (arc.from, arc.to)
+                    || (other.from, other.to) == (arc.to, arc.from)
+            }) {
+                repairs.push(format!(
+                    "Duplicate arc between points {} and {} was removed",
+                    arc.from, arc.to
+                ));
+            } else {
+                arcs.push(*arc);
+            }
+        }
+        self.arcs = arcs;
+
+        let mut great_circles: Vec<SavedGreatCircle> = Vec::new();
+        for (i, mut gc) in self.great_circles.drain(..).enumerate() {
+            if !exists(gc.pole) {
+                repairs.push(format!(
+                    "Great circle {i} was removed, as its pole {} does not exist",
+                    gc.pole
+                ));
+            } else if great_circles.iter().any(|other| other.pole == gc.pole) {
+                repairs.push(format!(
+                    "Duplicate great circle with pole {} was removed",
+                    gc.pole
+                ));
+            } else {
+                if gc.color.as_deref().is_some_and(|color| !is_color(color)) {
+                    repairs.push(format!(
+                        "Great circle {i} had an invalid color and is drawn in the default one"
+                    ));
+                    gc.color = None;
+                }
+                great_circles.push(gc);
+            }
+        }
+        self.great_circles = great_circles;
+
+        let mut small_circles: Vec<SavedSmallCircle> = Vec::new();
+        for (i, mut sc) in self.small_circles.drain(..).enumerate() {
+            if !exists(sc.pole) || !sc.plane_distance.is_finite() {
+                repairs.push(format!(
+                    "Small circle {i} was removed, as its pole {} or plane distance is invalid",
+                    sc.pole
+                ));
+                continue;
+            }
+            if small_circles.iter().any(|other| other.pole == sc.pole) {
+                repairs.push(format!(
+                    "Duplicate small circle with pole {} was removed",
+                    sc.pole
+                ));
+                continue;
+            }
+            if !(-1.0..=1.0).contains(&sc.plane_distance) {
+                repairs.push(format!(
+                    "Plane distance {} of small circle {i} was limited to [-1, 1]",
+                    sc.plane_distance
+                ));
+                sc.plane_distance = sc.plane_distance.clamp(-1.0, 1.0);
+            }
+            if let Some(through) = sc.through.filter(|&through| !exists(through)) {
+                repairs.push(format!(
+                    "Small circle {i} no longer follows point {through}, which does not exist"
+                ));
+                sc.through = None;
+            }
+            if sc.color.as_deref().is_some_and(|color| !is_color(color)) {
+                repairs.push(format!(
+                    "Small circle {i} had an invalid color and is drawn in the default one"
+                ));
+                sc.color = None;
+            }
+            small_circles.push(sc);
+        }
+        self.small_circles = small_circles;
+
+        let points = self.points();
+        let mut constraints = Vec::new();
+        for (i, constraint) in self.constraints.drain(..).enumerate() {
+            let valid_angle = match constraint {
+                Constraint::Distance { angle, .. } => angle.is_finite(),
+                _ => true,
+            };
+            if constraint.points().into_iter().all(exists) && valid_angle {
+                constraints.push(constraint);
+            } else {
+                repairs.push(format!(
+                    "Constraint {} ({}) was removed, as it refers to missing points",
+                    i + 1,
+                    constraint.describe(&points)
+                ));
+            }
+        }
+        self.constraints = constraints;
+
+        for (i, trace) in self.traces.iter_mut().enumerate() {
+            if let Some(point) = trace.point.filter(|&point| !exists(point)) {
+                repairs.push(format!(
+                    "Trace {} stopped following point {point}, which does not exist",
+                    i + 1
+                ));
+                trace.point = None;
+            }
+            let length = trace.path.len();
+            trace.path = trace.path.iter().filter_map(|&v| unit(v)).collect();
+            if trace.path.len() < length {
+                repairs.push(format!(
+                    "{} invalid positions were removed from trace {}",
+                    length - trace.path.len(),
+                    i + 1
+                ));
+            }
+        }
+
+        let mut grouped = Vec::new();
+        let mut groups = Vec::new();
+        for (i, group) in self.groups.drain(..).enumerate() {
+            let members: Vec<usize> = group
+                .iter()
+                .copied()
+                .filter(|&id| exists(id) && !grouped.contains(&id))
+                .collect();
+            if members.len() < group.len() {
+                repairs.push(format!(
+                    "Group {} lost members that are missing or already in another group",
+                    i + 1
+                ));
+            }
+            if members.len() >= 2 {
+                grouped.extend(&members);
+                groups.push(members);
+            } else {
+                repairs.push(format!(
+                    "Group {} was removed, as fewer than 2 points are left in it",
+                    i + 1
+                ));
+            }
+        }
+        self.groups = groups;
+
+        if let Some(reference) = self.display.grid.reference {
+            if !self.great_circles.iter().any(|gc| gc.pole == reference) {
+                repairs.push(format!(
+                    "The grid was aligned to a great circle with pole {reference}, which does not exist; it now uses the absolute frame"
+                ));
+                self.display.grid.reference = None;
+            }
+        }
+        // Same limits as the grid settings; larger ranges or finer steps would
+        // make drawing the grid take unbounded time and memory
+        let defaults = GridConfig::default();
+        let grid = &mut self.display.grid;
+        for (label, value, default, (min, max)) in [
+            (
+                "latitude step",
+                &mut grid.lat_step,
+                defaults.lat_step,
+                (1.0, 90.0),
+            ),
+            (
+                "longitude step",
+                &mut grid.lon_step,
+                defaults.lon_step,
+                (1.0, 180.0),
+            ),
+            (
+                "lowest latitude",
+                &mut grid.lat_min,
+                defaults.lat_min,
+                (-90.0, 90.0),
+            ),
+            (
+                "highest latitude",
+                &mut grid.lat_max,
+                defaults.lat_max,
+                (-90.0, 90.0),
+            ),
+            (
+                "lowest longitude",
+                &mut grid.lon_min,
+                defaults.lon_min,
+                (0.0, 360.0),
+            ),
+            (
+                "highest longitude",
+                &mut grid.lon_max,
+                defaults.lon_max,
+                (0.0, 360.0),
+            ),
+        ] {
+            if !(min..=max).contains(value) {
+                repairs.push(format!(
+                    "Grid {label} {value} was outside {min}°–{max}° and has been adjusted"
+                ));
+                *value = if value.is_finite() {
+                    value.clamp(min, max)
+                } else {
+                    default
+                };
+            }
+        }
+
+        if !self.view.quaternion.is_finite() {
+            repairs.push("The saved view orientation was invalid and has been reset".to_string());
+            self.view.quaternion = Quaternion::identity();
+        }
+        if !(0.5..=2.0).contains(&self.view.zoom) {
+            repairs.push(format!(
+                "Zoom {} was outside 50%–200% and has been adjusted",
+                self.view.zoom
+            ));
+            self.view.zoom = if self.view.zoom.is_finite() {
+                self.view.zoom.clamp(0.5, 2.0)
+            } else {
+                1.0
+            };
+        }
+
+        Ok(repairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn point(position: Vec3) -> SavedPoint {
+        SavedPoint {
+            position,
+            name: String::new(),
+            movable: true,
+            removable: true,
+            hidden: false,
+            construction: None,
+            color: None,
+        }
+    }
+
+    /// Valid file with three points on the axes and nothing else
+    fn file() -> SaveFile {
+        SaveFile {
+            version: SAVE_VERSION,
+            points: vec![
+                point([1.0, 0.0, 0.0]),
+                point([0.0, 1.0, 0.0]),
+                point([0.0, 0.0, 1.0]),
+            ],
+            arcs: Vec::new(),
+            great_circles: Vec::new(),
+            small_circles: Vec::new(),
+            constraints: Vec::new(),
+            traces: Vec::new(),
+            groups: Vec::new(),
+            view: SavedView::default(),
+            display: SavedDisplay::default(),
+        }
+    }
+
+    fn great_circle(pole: usize) -> SavedGreatCircle {
+        SavedGreatCircle {
+            pole,
+            name: String::new(),
+            color: None,
+        }
+    }
+
+    fn small_circle(pole: usize, plane_distance: f64) -> SavedSmallCircle {
+        SavedSmallCircle {
+            pole,
+            plane_distance,
+            name: String::new(),
+            through: None,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn unversioned_files_are_migrated() {
+        let text = json!({
+            "points": [
+                [[0.0, 0.0, 1.0], "A", true, false],
+                [[0.0, 0.0, -1.0], "B", false, true],
+                [[1.0, 0.0, 0.0], "C", true, true],
+            ],
+            "arcs": [[0, 2]],
+            "great_circles": [[0, "equator"]],
+            "small_circles": [[0, 0.5, "parallel"], [2, 0.0, "meridian"]],
+            "constructions": [null, Construction::Antipode(0)],
+            "small_circle_through": [null, 1],
+            "constraints": [Constraint::OnCircle { point: 2, pole: 0 }],
+        })
+        .to_string();
+        let file = SaveFile::from_json(&text).unwrap();
+
+        assert_eq!(file.version, SAVE_VERSION);
+        assert_eq!(file.points.len(), 3);
+        assert_eq!(file.points[0].name, "A");
+        assert!(file.points[0].movable && !file.points[0].removable);
+        assert!(!file.points[1].movable && file.points[1].removable);
+        assert_eq!(file.points[0].construction, None);
+        assert_eq!(file.points[1].construction, Some(Construction::Antipode(0)));
+        // The parallel lists may be shorter than the ones they describe
+        assert_eq!(file.points[2].construction, None);
+        assert_eq!(file.arcs, vec![SavedArc { from: 0, to: 2 }]);
+        assert_eq!(file.great_circles[0].name, "equator");
+        assert_eq!(file.small_circles[0].plane_distance, 0.5);
+        assert_eq!(file.small_circles[0].through, None);
+        assert_eq!(file.small_circles[1].through, Some(1));
+        assert_eq!(file.constraints.len(), 1);
+        assert!(file.groups.is_empty());
+        assert_eq!(file.view, SavedView::default());
+
+        // Saving again writes the current format, which reads back unchanged
+        assert_eq!(SaveFile::from_json(&file.to_json().unwrap()), Ok(file));
+    }
+
+    #[test]
+    fn unreadable_versions_are_rejected() {
+        let newer = format!(r#"{{"version": {}, "points": []}}"#, SAVE_VERSION + 1);
+        assert!(SaveFile::from_json(&newer).unwrap_err().contains("newer"));
+        let invalid = SaveFile::from_json(r#"{"version": "1", "points": []}"#);
+        assert!(invalid.unwrap_err().starts_with("Invalid format version"));
+        let legacy = SaveFile::from_json(r#"{"points": [[[0, 0, 1], "A"]]}"#);
+        assert!(legacy
+            .unwrap_err()
+            .starts_with("Failed to read unversioned file"));
+        assert!(SaveFile::from_json("{").is_err());
+    }
+
+    #[test]
+    fn valid_files_need_no_repairs() {
+        let mut file = file();
+        file.arcs.push(SavedArc { from: 0, to: 1 });
+        file.great_circles.push(great_circle(2));
+        file.small_circles.push(small_circle(0, 0.5));
+        file.groups.push(vec![0, 1]);
+        file.display.grid.reference = Some(2);
+        let original = file.clone();
+        assert_eq!(file.validate(), Ok(Vec::new()));
+        assert_eq!(file, original);
+    }
+
+    #[test]
+    fn points_without_a_direction_are_rejected() {
+        let mut file = file();
+        file.points[1].position = [0.0, 0.0, 0.0];
+        assert!(file
+            .validate()
+            .unwrap_err()
+            .starts_with("Point 1 has no valid position"));
+        file.points[1].position = [f64::NAN, 0.0, 1.0];
+        assert!(file.validate().is_err());
+    }
+
+    #[test]
+    fn points_are_repaired() {
+        let mut file = file();
+        file.points[0].position = [2.0, 0.0, 0.0];
+        file.points[1].construction = Some(Construction::Antipode(1));
+        file.points[2].construction = Some(Construction::GreatCirclePole(0, 7));
+        file.points[0].color = Some("red".to_string());
+        file.points[1].color = Some("#00ff00".to_string());
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Point 0 was moved onto the sphere".to_string(),
+                "Point 0 had an invalid color and is drawn in the default one".to_string(),
+                "Point 1 was derived from point 1, which is invalid; it is now an ordinary point"
+                    .to_string(),
+                "Point 2 was derived from point 7, which is invalid; it is now an ordinary point"
+                    .to_string(),
+            ])
+        );
+        assert_eq!(file.points[0].position, [1.0, 0.0, 0.0]);
+        assert_eq!(file.points[0].color, None);
+        assert_eq!(file.points[1].color.as_deref(), Some("#00ff00"));
+        assert!(file.points.iter().all(|point| point.construction.is_none()));
+    }
+
+    #[test]
+    fn broken_and_duplicate_lines_are_removed() {
+        let mut file = file();
+        file.arcs = vec![
+            SavedArc { from: 0, to: 1 },
+            SavedArc { from: 1, to: 1 },
+            SavedArc { from: 0, to: 5 },
+            SavedArc { from: 1, to: 0 },
+        ];
+        file.great_circles = vec![great_circle(4), great_circle(0), great_circle(0)];
+        file.great_circles[1].color = Some("#12345".to_string());
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Arc 1 between points 1 and 1 was removed, as it does not join two existing points"
+                    .to_string(),
+                "Arc 2 between points 0 and 5 was removed, as it does not join two existing points"
+                    .to_string(),
+                "Duplicate arc between points 1 and 0 was removed".to_string(),
+                "Great circle 0 was removed, as its pole 4 does not exist".to_string(),
+                "Great circle 1 had an invalid color and is drawn in the default one".to_string(),
+                "Duplicate great circle with pole 0 was removed".to_string(),
+            ])
+        );
+        assert_eq!(file.arcs, vec![SavedArc { from: 0, to: 1 }]);
+        assert_eq!(file.great_circles, vec![great_circle(0)]);
+    }
+
+    #[test]
+    fn small_circles_are_repaired() {
+        let mut file = file();
+        let mut through_missing = small_circle(1, 0.3);
+        through_missing.through = Some(9);
+        through_missing.color = Some("#GGGGGG".to_string());
+        file.small_circles = vec![
+            small_circle(9, 0.5),
+            small_circle(0, f64::NAN),
+            small_circle(0, 1.5),
+            small_circle(0, 0.2),
+            through_missing,
+        ];
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Small circle 0 was removed, as its pole 9 or plane distance is invalid"
+                    .to_string(),
+                "Small circle 1 was removed, as its pole 0 or plane distance is invalid"
+                    .to_string(),
+                "Plane distance 1.5 of small circle 2 was limited to [-1, 1]".to_string(),
+                "Duplicate small circle with pole 0 was removed".to_string(),
+                "Small circle 4 no longer follows point 9, which does not exist".to_string(),
+                "Small circle 4 had an invalid color and is drawn in the default one".to_string(),
+            ])
+        );
+        assert_eq!(
+            file.small_circles,
+            vec![small_circle(0, 1.0), small_circle(1, 0.3)]
+        );
+    }
+
+    #[test]
+    fn constraints_and_traces_are_repaired() {
+        let mut file = file();
+        file.points[0].name = "A".to_string();
+        file.constraints = vec![
+            Constraint::Distance {
+                a: 0,
+                b: 1,
+                angle: 1.0,
+            },
+            Constraint::OnCircle { point: 0, pole: 3 },
+            Constraint::Distance {
+                a: 0,
+                b: 2,
+                angle: f64::INFINITY,
+            },
+        ];
+        file.traces = vec![
+            Trace {
+                point: Some(2),
+                path: vec![[0.0, 0.0, 1.0], [0.0, 0.0, 0.0], [f64::NAN, 0.0, 0.0]],
+                recording: false,
+            },
+            Trace {
+                point: Some(5),
+                path: vec![[0.0, 0.0, 2.0]],
+                recording: true,
+            },
+        ];
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Constraint 2 (A on circle of pole #3) was removed, as it refers to missing points"
+                    .to_string(),
+                "Constraint 3 (|A–#2| = inf°) was removed, as it refers to missing points"
+                    .to_string(),
+                "2 invalid positions were removed from trace 1".to_string(),
+                "Trace 2 stopped following point 5, which does not exist".to_string(),
+            ])
+        );
+        assert_eq!(file.constraints.len(), 1);
+        assert_eq!(file.traces[0].path, vec![[0.0, 0.0, 1.0]]);
+        assert_eq!(file.traces[1].point, None);
+        assert_eq!(file.traces[1].path, vec![[0.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn groups_grid_and_view_are_repaired() {
+        let mut file = file();
+        file.points.push(point([0.0, 0.0, -1.0]));
+        file.groups = vec![vec![0, 1, 8], vec![1, 2], vec![2, 3]];
+        file.great_circles.push(great_circle(0));
+        file.display.grid.reference = Some(1);
+        file.view.quaternion = Quaternion::new(f64::NAN, 0.0, 0.0, 0.0);
+        file.view.zoom = 5.0;
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Group 1 lost members that are missing or already in another group".to_string(),
+                "Group 2 lost members that are missing or already in another group".to_string(),
+                "Group 2 was removed, as fewer than 2 points are left in it".to_string(),
+                "The grid was aligned to a great circle with pole 1, which does not exist; it now uses the absolute frame"
+                    .to_string(),
+                "The saved view orientation was invalid and has been reset".to_string(),
+                "Zoom 5 was outside 50%–200% and has been adjusted".to_string(),
+            ])
+        );
+        assert_eq!(file.groups, vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(file.display.grid.reference, None);
+        assert_eq!(file.view.quaternion, Quaternion::identity());
+        assert_eq!(file.view.zoom, 2.0);
+
+        file.view.zoom = f64::NAN;
+        assert_eq!(file.validate().unwrap().len(), 1);
+        assert_eq!(file.view.zoom, 1.0);
+    }
+
+    #[test]
+    fn grid_limits_are_repaired() {
+        let mut file = file();
+        file.display.grid.lat_step = 0.0;
+        file.display.grid.lon_step = f64::NAN;
+        file.display.grid.lat_min = -100.0;
+        file.display.grid.lon_min = -1e12;
+        file.display.grid.lon_max = 1e12;
+
+        assert_eq!(
+            file.validate(),
+            Ok(vec![
+                "Grid latitude step 0 was outside 1°–90° and has been adjusted".to_string(),
+                "Grid longitude step NaN was outside 1°–180° and has been adjusted".to_string(),
+                "Grid lowest latitude -100 was outside -90°–90° and has been adjusted".to_string(),
+                "Grid lowest longitude -1000000000000 was outside 0°–360° and has been adjusted"
+                    .to_string(),
+                "Grid highest longitude 1000000000000 was outside 0°–360° and has been adjusted"
+                    .to_string(),
+            ])
+        );
+        let grid = &file.display.grid;
+        assert_eq!((grid.lat_step, grid.lon_step), (1.0, 30.0));
+        assert_eq!((grid.lat_min, grid.lat_max), (-90.0, 90.0));
+        assert_eq!((grid.lon_min, grid.lon_max), (0.0, 360.0));
+        // The repaired grid draws in bounded time
+        file.display.show_grid = true;
+        assert!(scene_to_svg(&file.scene()).len() < 1_000_000);
+    }
+}
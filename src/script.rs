@@ -0,0 +1,564 @@
+use crate::*;
+use std::collections::HashSet;
+
+/// Opening comment of generated scripts
+const SCRIPT_HEADER: &str = "\
+// Celestial sphere construction script
+// One command per line, as typed in the command console; lines starting with
+// // are comments. Coordinates are in the absolute frame. The view, display
+// settings, constraints, groups and traces are not part of the script.";
+
+/// Statement of a script, with the line it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptStatement {
+    /// Line number, counting from 1
+    pub line: usize,
+    pub text: String,
+    pub command: Command,
+}
+
+/// Construction of a diagram as a list of commands, which can be replayed with
+/// different parameter values
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Script {
+    pub statements: Vec<ScriptStatement>,
+}
+
+/// Text of a line before its `//` comment, if any
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '/' if !quoted && line[i + 1..].starts_with('/') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Parses a script; blank lines and comments are skipped
+pub fn parse_script(text: &str) -> Result<Script, String> {
+    let mut statements = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let text = strip_comment(line).trim();
+        if text.is_empty() {
+            continue;
+        }
+        let command = parse_command(text).map_err(|error| format!("Line {}: {error}", i + 1))?;
+        statements.push(ScriptStatement {
+            line: i + 1,
+            text: text.to_string(),
+            command,
+        });
+    }
+    Ok(Script { statements })
+}
+
+impl Script {
+    /// Parameters set to plain numbers, which a replay may change, with their values
+    pub fn parameters(&self) -> Vec<(String, f64)> {
+        let mut parameters: Vec<(String, f64)> = Vec::new();
+        for statement in &self.statements {
+            let Command::Parameter { name, value } = &statement.command else {
+                continue;
+            };
+            if let Some(value) = value.constant() {
+                parameters.retain(|(parameter, _)| parameter != name);
+                parameters.push((name.clone(), value));
+            }
+        }
+        parameters
+    }
+
+    /// Runs the first `count` statements, giving the listed parameters their
+    /// values instead of the script's. Fails with the index of the statement
+    /// that could not be run and the reason.
+    pub fn run(
+        &self,
+        count: usize,
+        parameters: &[(String, f64)],
+        diagram: &mut Diagram,
+    ) -> Result<(), (usize, String)> {
+        for (i, statement) in self.statements.iter().take(count).enumerate() {
+            let overridden = match &statement.command {
+                Command::Parameter { name, .. } => parameters
+                    .iter()
+                    .find(|(parameter, _)| parameter == name)
+                    .map(|&(_, value)| Command::Parameter {
+                        name: name.clone(),
+                        value: Expression::Number(value),
+                    }),
+                Command::Call { .. } => None,
+            };
+            run_command(overridden.as_ref().unwrap_or(&statement.command), diagram)
+                .map_err(|error| (i, error))?;
+        }
+        Ok(())
+    }
+}
+
+/// Number with at most six decimals, without trailing zeros
+fn script_number(value: f64) -> String {
+    let text = format!("{value:.6}");
+    match text.trim_end_matches('0').trim_end_matches('.') {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+/// How a name is written in a script: bare if it reads as a name, quoted
+/// otherwise, and `None` if the command language cannot refer to it or a
+/// line break would split the statement
+fn script_name(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    if first == '#' || name.contains(['"', '\n', '\r']) {
+        return None;
+    }
+    let bare = (first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '\''));
+    Some(if bare {
+        name.to_string()
+    } else {
+        format!("\"{name}\"")
+    })
+}
+
+/// Adds `id` to `order` after the points it is constructed from. A point in a
+/// cycle of constructions comes before one of its parents and is written as free.
+fn write_order(id: usize, points: &[Point], visited: &mut [bool], order: &mut Vec<usize>) {
+    if visited[id] {
+        return;
+    }
+    visited[id] = true;
+    if let Some(construction) = points[id].construction {
+        for parent in construction.parents() {
+            if parent < points.len() {
+                write_order(parent, points, visited, order);
+            }
+        }
+    }
+    order.push(id);
+}
+
+/// Writes the diagram as a construction script: free points by their absolute
+/// coordinates, derived points by their construction, then the circles and arcs
+pub fn diagram_to_script(
+    points: &[Point],
+    arcs: &[(usize, usize)],
+    great_circles: &[GreatCircle],
+    small_circles: &[SmallCircle],
+) -> String {
+    let grid = GridConfig::default();
+    let mut lines = vec![SCRIPT_HEADER.to_string(), String::new()];
+    // Names already given, which later objects cannot reuse
+    let mut taken = HashSet::new();
+    // Writes a statement creating an object, named if the name can be written
+    // and is not taken; returns the name as written
+    let mut statement = |name: &str, call: String, lines: &mut Vec<String>| {
+        let target = script_name(name).filter(|_| taken.insert(name.to_string()));
+        match &target {
+            Some(target) => lines.push(format!("{target} = {call}")),
+            None if name.is_empty() => lines.push(call),
+            None => {
+                lines.push(format!(
+                    "// The next object is named {name:?} in the diagram"
+                ));
+                lines.push(call);
+            }
+        }
+        target
+    };
+
+    // How each point is referred to, once it has been written
+    let mut references: Vec<Option<String>> = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut order = Vec::new();
+    for id in 0..points.len() {
+        write_order(id, points, &mut visited, &mut order);
+    }
+    for (written, id) in order.into_iter().enumerate() {
+        let parents = points[id]
+            .construction
+            .map(|construction| construction.parents())
+            .unwrap_or_default();
+        let reference = |parent: usize| references[parent].clone().unwrap_or_default();
+        let call = match points[id].construction {
+            Some(construction)
+                if parents
+                    .iter()
+                    .all(|&p| p < points.len() && references[p].is_some()) =>
+            {
+                match construction {
+                    Construction::Antipode(p) => format!("antipode({})", reference(p)),
+                    Construction::GreatCirclePole(a, b) => {
+                        format!("pole({}, {})", reference(a), reference(b))
+                    }
+                    Construction::SmallCirclePole(a, b, c) => {
+                        format!("pole({}, {}, {})", reference(a), reference(b), reference(c))
+                    }
+                    Construction::Intersection(a, b) => {
+                        format!("intersect({}, {})", reference(a), reference(b))
+                    }
+                }
+            }
+            _ => {
+                let [lat, lon] = grid.frame_coordinates(points, points[id].absolute);
+                format!(
+                    "point(lat={}, lon={})",
+                    script_number(lat),
+                    script_number(lon)
+                )
+            }
+        };
+        let target = statement(&points[id].name, call, &mut lines);
+        let reference = target.unwrap_or(format!("#{written}"));
+        if points[id].hidden {
+            lines.push(format!("hide({reference})"));
+        }
+        references[id] = Some(reference);
+    }
+    let reference = |id: usize| references[id].clone().unwrap_or_default();
+
+    if !great_circles.is_empty() || !small_circles.is_empty() {
+        lines.push(String::new());
+    }
+    for great_circle in great_circles {
+        let call = format!("gc({})", reference(great_circle.pole));
+        statement(&great_circle.name, call, &mut lines);
+    }
+    for small_circle in small_circles {
+        let pole = reference(small_circle.pole);
+        let call = match small_circle.through {
+            Some(through) => format!("sc({pole}, {})", reference(through)),
+            None => format!(
+                "sc({pole}, radius={}°)",
+                script_number(small_circle.plane_distance.acos().to_degrees())
+            ),
+        };
+        statement(&small_circle.name, call, &mut lines);
+    }
+
+    if !arcs.is_empty() {
+        lines.push(String::new());
+    }
+    for &(a, b) in arcs {
+        lines.push(format!("arc({}, {})", reference(a), reference(b)));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// A script being replayed into the diagram
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptReplay {
+    pub script: Script,
+    /// Values chosen for the script's parameters
+    pub parameters: Vec<(String, f64)>,
+    /// Number of statements the user asked to run
+    pub target: usize,
+    /// Number of statements that ran
+    pub step: usize,
+    /// Index of the statement that failed, with the reason
+    pub error: Option<(usize, String)>,
+}
+
+impl ScriptReplay {
+    pub fn new(script: Script) -> Self {
+        Self {
+            parameters: script.parameters(),
+            script,
+            target: 0,
+            step: 0,
+            error: None,
+        }
+    }
+
+    /// Replaces the diagram with the result of the first `count` statements.
+    /// The view is kept; the grid returns to the absolute frame.
+    pub fn replay(
+        &mut self,
+        count: usize,
+        mut points: Signal<Vec<Point>>,
+        mut arcs: Signal<Vec<(usize, usize)>>,
+        mut great_circles: Signal<Vec<GreatCircle>>,
+        mut small_circles: Signal<Vec<SmallCircle>>,
+        mut state: Signal<State>,
+    ) {
+        let count = count.min(self.script.statements.len());
+        let mut new_points = Vec::new();
+        let mut new_arcs = Vec::new();
+        let mut new_great_circles = Vec::new();
+        let mut new_small_circles = Vec::new();
+        let mut parameters = Vec::new();
+        let quaternion = state.read().quaternion;
+        let result = self.script.run(
+            count,
+            &self.parameters,
+            &mut Diagram {
+                points: &mut new_points,
+                arcs: &mut new_arcs,
+                great_circles: &mut new_great_circles,
+                small_circles: &mut new_small_circles,
+                parameters: &mut parameters,
+                grid: &GridConfig::default(),
+                quaternion,
+            },
+        );
+
+        self.target = count;
+        self.step = result.as_ref().err().map_or(count, |&(i, _)| i);
+        self.error = result.err();
+        points.set(new_points);
+        arcs.set(new_arcs);
+        great_circles.set(new_great_circles);
+        small_circles.set(new_small_circles);
+
+        let mut state = state.write();
+        state.clear_selection();
+        state.constraints.clear();
+        state.traces.clear();
+        state.groups.clear();
+        state.grid.reference = None;
+    }
+}
+
+/// Steps through a loaded script, with its parameters open to change
+#[component]
+pub fn ScriptReplayPanel(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+    mut script_replay: Signal<Option<ScriptReplay>>,
+) -> Element {
+    let Some(replay) = script_replay() else {
+        return rsx! {};
+    };
+    let total = replay.script.statements.len();
+    let mut go_to = move |count: usize| {
+        if let Some(replay) = script_replay.write().as_mut() {
+            replay.replay(count, points, arcs, great_circles, small_circles, state);
+        }
+    };
+    let failed = replay.error.as_ref().map(|(i, _)| *i);
+
+    rsx! {
+        div { class: "script-panel",
+            h3 { "Construction script" }
+            if !replay.parameters.is_empty() {
+                div { class: "export-options",
+                    for (i , (name , value)) in replay.parameters.iter().enumerate() {
+                        label { key: "{name}",
+                            "{name} "
+                            input {
+                                r#type: "number",
+                                step: "any",
+                                value: "{value}",
+                                onchange: move |evt| {
+                                    let Ok(value) = evt.value().parse::<f64>() else {
+                                        return;
+                                    };
+                                    let target = match script_replay.write().as_mut() {
+                                        Some(replay) => {
+                                            replay.parameters[i].1 = value;
+                                            replay.target
+                                        }
+                                        None => return,
+                                    };
+                                    go_to(target);
+                                },
+                            }
+                        }
+                    }
+                }
+            }
+            ol { class: "script-steps",
+                for (i , statement) in replay.script.statements.iter().enumerate() {
+                    li {
+                        key: "{i}",
+                        class: if failed == Some(i) { "script-failed" } else if i < replay.step { "script-done" },
+                        title: "Line {statement.line} — click to run up to here",
+                        onclick: move |_| go_to(i + 1),
+                        "{statement.text}"
+                    }
+                }
+            }
+            if let Some((i, message)) = &replay.error {
+                p { class: "dialog-error",
+                    "Line {replay.script.statements[*i].line}: {message}"
+                }
+            }
+            p { "{replay.step} of {total} steps" }
+            div { class: "script-buttons",
+                button { onclick: move |_| go_to(0), "Restart" }
+                button {
+                    disabled: replay.step >= total,
+                    onclick: move |_| go_to(replay.step + 1),
+                    "Step"
+                }
+                button {
+                    disabled: replay.step >= total,
+                    onclick: move |_| go_to(total),
+                    "Run to end"
+                }
+                button { onclick: move |_| script_replay.set(None), "Close" }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Diagram built by running a script from scratch
+    #[derive(Default)]
+    struct Built {
+        points: Vec<Point>,
+        arcs: Vec<(usize, usize)>,
+        great_circles: Vec<GreatCircle>,
+        small_circles: Vec<SmallCircle>,
+    }
+
+    fn build(script: &Script, parameters: &[(String, f64)]) -> Result<Built, (usize, String)> {
+        let mut built = Built::default();
+        let grid = GridConfig::default();
+        script.run(
+            script.statements.len(),
+            parameters,
+            &mut Diagram {
+                points: &mut built.points,
+                arcs: &mut built.arcs,
+                great_circles: &mut built.great_circles,
+                small_circles: &mut built.small_circles,
+                parameters: &mut Vec::new(),
+                grid: &grid,
+                quaternion: Quaternion::identity(),
+            },
+        )?;
+        Ok(built)
+    }
+
+    fn to_script(built: &Built) -> String {
+        diagram_to_script(
+            &built.points,
+            &built.arcs,
+            &built.great_circles,
+            &built.small_circles,
+        )
+    }
+
+    const CONSTRUCTION: &str = "\
+phi = 52 // latitude of the observer
+Z = point(lat=90, lon=0)
+P = point(lat=phi, lon=30)
+\"Q 2\" = point(lat=-20, lon=100)
+horizon = gc(Z)
+meridian = gc(Z, P)
+S = intersect(horizon, meridian)
+N = antipode(S)
+hide(N)
+T = pole(Z, P, \"Q 2\")
+sc(P, radius=15°)
+sc(Z, \"Q 2\")
+arc(P, \"Q 2\")
+";
+
+    fn assert_same(a: &Built, b: &Built) {
+        assert_eq!(a.points.len(), b.points.len());
+        for (p, q) in a.points.iter().zip(&b.points) {
+            assert!(
+                arc_distance(p.absolute, q.absolute) < 1e-6,
+                "{p:?} != {q:?}"
+            );
+            assert_eq!(p.name, q.name);
+            assert_eq!(p.hidden, q.hidden);
+            assert_eq!(p.construction, q.construction);
+        }
+        assert_eq!(a.arcs, b.arcs);
+        let poles = |built: &Built| {
+            built
+                .great_circles
+                .iter()
+                .map(|gc| (gc.pole, gc.name.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(poles(a), poles(b));
+        assert_eq!(a.small_circles.len(), b.small_circles.len());
+        for (s, t) in a.small_circles.iter().zip(&b.small_circles) {
+            assert_eq!((s.pole, s.through), (t.pole, t.through));
+            assert!((s.plane_distance - t.plane_distance).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn scripts_round_trip() {
+        let original = build(&parse_script(CONSTRUCTION).unwrap(), &[]).unwrap();
+        let text = to_script(&original);
+        let replayed = build(&parse_script(&text).unwrap(), &[]).unwrap();
+        assert_same(&original, &replayed);
+        // Writing the replayed diagram gives the same script
+        assert_eq!(to_script(&replayed), text);
+    }
+
+    #[test]
+    fn unwritable_names_become_comments() {
+        let mut original = build(&parse_script(CONSTRUCTION).unwrap(), &[]).unwrap();
+        original.points[1].name = "Z".to_string();
+        original.points[2].name = "#2".to_string();
+        original.points[3].name = "two\nlines".to_string();
+        let text = to_script(&original);
+        assert!(text.contains("// The next object is named \"Z\" in the diagram"));
+        assert!(text.contains("// The next object is named \"#2\" in the diagram"));
+        assert!(text.contains("// The next object is named \"two\\nlines\" in the diagram"));
+        let replayed = build(&parse_script(&text).unwrap(), &[]).unwrap();
+        assert_eq!(replayed.points.len(), original.points.len());
+        assert_eq!(replayed.points[1].name, "");
+        for (p, q) in original.points.iter().zip(&replayed.points) {
+            assert!(arc_distance(p.absolute, q.absolute) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn parameters_can_be_overridden() {
+        let script = parse_script(CONSTRUCTION).unwrap();
+        assert_eq!(script.parameters(), vec![("phi".to_string(), 52.0)]);
+        let built = build(&script, &[("phi".to_string(), 10.0)]).unwrap();
+        let [lat, _] =
+            GridConfig::default().frame_coordinates(&built.points, built.points[1].absolute);
+        assert!((lat - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parameters_need_constant_values() {
+        let script = parse_script("a = 10\nb = a * 2\na = 20").unwrap();
+        assert_eq!(script.parameters(), vec![("a".to_string(), 20.0)]);
+    }
+
+    #[test]
+    fn errors_name_their_line() {
+        assert_eq!(
+            parse_script("// header\n\nA = point(1, 2\n").unwrap_err(),
+            "Line 3: Column 15: expected `,` or `)`"
+        );
+        let script = parse_script("A = point(0, 0)\ngc(B)").unwrap();
+        assert_eq!(
+            build(&script, &[]).err(),
+            Some((1, "Nothing is named B".to_string()))
+        );
+    }
+
+    #[test]
+    fn comments_respect_quotes() {
+        assert_eq!(
+            strip_comment("\"a//b\" = point(0, 0) // c"),
+            "\"a//b\" = point(0, 0) "
+        );
+        let script = parse_script("\"a//b\" = point(0, 0)").unwrap();
+        assert_eq!(script.statements[0].line, 1);
+        assert_eq!(build(&script, &[]).unwrap().points[0].name, "a//b");
+    }
+}
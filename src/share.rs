@@ -0,0 +1,175 @@
+use crate::*;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use std::io::{Read, Write};
+
+/// Start of a URL fragment holding a shared diagram
+const SHARE_PREFIX: &str = "#diagram=";
+/// Largest decompressed diagram accepted from a link
+const MAX_SHARED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Encodes the diagram as a URL fragment: compact JSON, deflated and base64url encoded
+pub fn encode_share_fragment(save_file: &SaveFile) -> Result<String, String> {
+    let json = serde_json::to_vec(save_file).map_err(|e| e.to_string())?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&json).map_err(|e| e.to_string())?;
+    let compressed = encoder.finish().map_err(|e| e.to_string())?;
+    Ok(format!(
+        "{SHARE_PREFIX}{}",
+        URL_SAFE_NO_PAD.encode(compressed)
+    ))
+}
+
+/// Reads the diagram from a URL fragment written by `encode_share_fragment`.
+/// Returns `None` if the fragment does not hold a diagram.
+pub fn decode_share_fragment(fragment: &str) -> Option<Result<String, String>> {
+    let encoded = fragment.strip_prefix(SHARE_PREFIX)?;
+    let decode = || {
+        let compressed = URL_SAFE_NO_PAD
+            .decode(encoded.trim_end_matches('='))
+            .map_err(|_| "The link is damaged (invalid characters)".to_string())?;
+        let mut json = String::new();
+        DeflateDecoder::new(compressed.as_slice())
+            .take(MAX_SHARED_BYTES)
+            .read_to_string(&mut json)
+            .map_err(|_| "The link is damaged (cannot be decompressed)".to_string())?;
+        Ok(json)
+    };
+    Some(decode())
+}
+
+/// Address of the app showing the current diagram
+pub fn share_link(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+) -> Result<String, String> {
+    let save_file = SaveFile::new(
+        &points.read(),
+        &arcs.read(),
+        &great_circles.read(),
+        &small_circles.read(),
+        &state.read(),
+    );
+    let address = platform::app_address()?;
+    Ok(format!("{address}{}", encode_share_fragment(&save_file)?))
+}
+
+/// Whether the page was opened from a shared link
+pub fn has_shared_diagram() -> bool {
+    platform::location_fragment().is_some_and(|fragment| fragment.starts_with(SHARE_PREFIX))
+}
+
+/// Loads the diagram of a shared link the page was opened with, then removes it
+/// from the address so that reloading keeps later changes (through autosave).
+/// Returns `None` if the page was not opened from a shared link.
+pub fn load_shared_diagram(
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    state: Signal<State>,
+) -> Option<Result<Vec<String>, String>> {
+    let fragment = platform::location_fragment()?;
+    let json = decode_share_fragment(&fragment)?;
+    platform::clear_location_fragment();
+
+    Some(json.and_then(|json| {
+        restore_data_from_json(&json, points, arcs, great_circles, small_circles, state)
+    }))
+}
+
+/// Shows a link to the current diagram and copies it to the clipboard
+#[component]
+pub fn ShareDialog(link: String, on_close: EventHandler<()>) -> Element {
+    let mut copied = use_signal(|| None::<bool>);
+    let clipboard_link = link.clone();
+    use_hook(move || {
+        spawn(async move {
+            copied.set(Some(platform::copy_to_clipboard(&clipboard_link).await));
+        });
+    });
+
+    let message = match copied() {
+        Some(true) => "The link was copied to the clipboard.",
+        Some(false) => "Copy the link below to share the diagram.",
+        None => "Copying the link…",
+    };
+    let length = link.len();
+    rsx! {
+        div { class: "dialog-backdrop", onclick: move |_| on_close.call(()),
+            div {
+                class: "dialog",
+                role: "dialog",
+                onclick: move |evt| evt.stop_propagation(),
+                h3 { "Share link" }
+                p { "{message} Anyone opening it sees this diagram as it is now." }
+                input {
+                    id: "share-link",
+                    class: "share-link",
+                    readonly: true,
+                    value: "{link}",
+                    onfocus: move |_| platform::select_input_text("share-link"),
+                }
+                if length > 8000 {
+                    p { class: "dialog-error",
+                        "The link is {length} characters long; some browsers and chat apps shorten such links. Save a file instead if it does not open."
+                    }
+                }
+                button { onclick: move |_| on_close.call(()), "OK" }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fragment holding `json` as is, as a hand-made link could
+    fn fragment(json: &str) -> String {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(json.as_bytes()).unwrap();
+        format!(
+            "{SHARE_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(encoder.finish().unwrap())
+        )
+    }
+
+    #[test]
+    fn shared_diagrams_round_trip() {
+        let mut file =
+            SaveFile::from_json(r#"{"version": 1, "points": [{"position": [0, 0, 1]}]}"#).unwrap();
+        file.display.grid.lat_step = 15.0;
+        let json = decode_share_fragment(&encode_share_fragment(&file).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(SaveFile::from_json(&json), Ok(file));
+    }
+
+    #[test]
+    fn hostile_grids_are_clamped() {
+        let link = fragment(
+            r#"{"version":1,"points":[],"display":{"show_grid":true,"grid":{"lon_min":-1e12,"lon_max":1e12}}}"#,
+        );
+        let json = decode_share_fragment(&link).unwrap().unwrap();
+        let mut file = SaveFile::from_json(&json).unwrap();
+        assert_eq!(file.validate().map(|repairs| repairs.len()), Ok(2));
+        assert_eq!(file.display.grid.lon_min, 0.0);
+        assert_eq!(file.display.grid.lon_max, 360.0);
+    }
+
+    #[test]
+    fn damaged_links_are_reported() {
+        assert_eq!(decode_share_fragment("#other"), None);
+        let invalid = decode_share_fragment(&format!("{SHARE_PREFIX}a!b")).unwrap();
+        assert_eq!(
+            invalid,
+            Err("The link is damaged (invalid characters)".to_string())
+        );
+        let truncated = decode_share_fragment(&format!("{SHARE_PREFIX}AAAA")).unwrap();
+        assert!(truncated.is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::*;
+
+/// What a left click on the sphere does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tool {
+    /// Select and drag points, without adding new ones
+    Select,
+    /// Add points on empty parts of the sphere; select and drag existing ones
+    #[default]
+    Point,
+    /// Click two points to draw or remove the arc between them
+    Arc,
+    /// Click two points to draw or remove the great circle through them
+    GreatCircle,
+    /// Click the pole, then a point the small circle passes through
+    SmallCircle,
+    /// Click two points for their distance, a third for the angle at the second
+    Measure,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::Select,
+        Tool::Point,
+        Tool::Arc,
+        Tool::GreatCircle,
+        Tool::SmallCircle,
+        Tool::Measure,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Point => "Point",
+            Tool::Arc => "Arc",
+            Tool::GreatCircle => "Great circle",
+            Tool::SmallCircle => "Small circle",
+            Tool::Measure => "Measure",
+        }
+    }
+
+    /// Keyboard action that switches to the tool
+    pub fn action(self) -> KeyAction {
+        match self {
+            Tool::Select => KeyAction::SelectTool,
+            Tool::Point => KeyAction::PointTool,
+            Tool::Arc => KeyAction::ArcTool,
+            Tool::GreatCircle => KeyAction::GreatCircleTool,
+            Tool::SmallCircle => KeyAction::SmallCircleTool,
+            Tool::Measure => KeyAction::MeasureTool,
+        }
+    }
+
+    /// Two-click tools keep the first point selected until the second is clicked
+    fn needs_two_points(self) -> bool {
+        matches!(self, Tool::Arc | Tool::GreatCircle | Tool::SmallCircle)
+    }
+
+    /// What to do next with the tool, given the current selection
+    pub fn hint(self, points: &[Point], state: &State, keymap: &Keymap) -> String {
+        let selected = state.selected();
+        let name = |id: usize| point_label(points, id);
+        // Mentions the shortcut of `action` after `text`, as currently bound
+        let with_key = |text: &str, action: KeyAction, rest: &str| match keymap.binding(action) {
+            Some(binding) => format!("{text}; `{binding}` {rest}"),
+            None => text.to_string(),
+        };
+        match (self, selected) {
+            (Tool::Select, _) => {
+                "Click a point to select it, Shift + click to add to the selection; drag to move"
+                    .to_string()
+            }
+            (Tool::Point, _) if state.snap => {
+                "Click to add a point snapped onto a nearby great circle, Shift + click to add it to the selection"
+                    .to_string()
+            }
+            (Tool::Point, _) => {
+                "Click the sphere to add a point, Shift + click to add it to the selection"
+                    .to_string()
+            }
+            (Tool::Arc, &[first]) => format!("Click the other end of the arc from {}", name(first)),
+            (Tool::Arc, _) => "Click the first end of an arc".to_string(),
+            (Tool::GreatCircle, &[first]) => {
+                format!(
+                    "Click a second point on the great circle through {}",
+                    name(first)
+                )
+            }
+            (Tool::GreatCircle, _) => with_key(
+                "Click two points on the great circle",
+                KeyAction::GreatCircleAroundPole,
+                "draws one around a selected pole",
+            ),
+            (Tool::SmallCircle, &[pole]) => {
+                format!("Click a point on the small circle around {}", name(pole))
+            }
+            (Tool::SmallCircle, _) => with_key(
+                "Click the pole of the small circle",
+                KeyAction::SmallCircleThroughPoints,
+                "draws one through 3 selected points",
+            ),
+            (Tool::Measure, &[a, b]) => format!(
+                "Distance {}–{} = {:.4}° — click a third point for the angle at {}",
+                name(a),
+                name(b),
+                arc_distance(points[a].absolute, points[b].absolute).to_degrees(),
+                name(b)
+            ),
+            (Tool::Measure, &[a, b, c]) => {
+                let [_, _, _, _, angle, _, _] =
+                    spherical_triangle(points[a].absolute, points[b].absolute, points[c].absolute);
+                format!(
+                    "Angle at {} between {} and {} = {angle:.4}°, distance {}–{} = {:.4}°",
+                    name(b),
+                    name(a),
+                    name(c),
+                    name(b),
+                    name(c),
+                    arc_distance(points[b].absolute, points[c].absolute).to_degrees()
+                )
+            }
+            (Tool::Measure, &[_]) => "Click a second point to measure the distance".to_string(),
+            (Tool::Measure, _) => "Click two points to measure their distance".to_string(),
+        }
+    }
+}
+
+/// Draws or removes the arc between two points
+pub fn toggle_arc(mut arcs: Signal<Vec<(usize, usize)>>, a: usize, b: usize) {
+    if a == b {
+        return;
+    }
+    if arcs().contains(&(a, b)) || arcs().contains(&(b, a)) {
+        arcs.write().retain(|&arc| arc != (a, b) && arc != (b, a));
+    } else {
+        arcs.write().push((a, b));
+    }
+}
+
+/// Handles a click on an existing point with a tool other than select and point.
+/// The two-point tools act once a second point is clicked.
+pub fn use_tool_on_point(
+    id: usize,
+    points: Signal<Vec<Point>>,
+    arcs: Signal<Vec<(usize, usize)>>,
+    great_circles: Signal<Vec<GreatCircle>>,
+    small_circles: Signal<Vec<SmallCircle>>,
+    mut state: Signal<State>,
+) {
+    let tool = state.read().tool;
+    let first = match *state.read().selected() {
+        [first] if first != id => Some(first),
+        _ => None,
+    };
+    if tool == Tool::Measure {
+        let mut s = state.write();
+        if s.selected().len() >= 3 || s.selected().contains(&id) {
+            s.clear_selection();
+        }
+        s.select(id);
+        return;
+    }
+    if !tool.needs_two_points() {
+        return;
+    }
+
+    let Some(first) = first else {
+        let mut s = state.write();
+        s.clear_selection();
+        s.select(id);
+        return;
+    };
+    state.write().select(id);
+    match tool {
+        Tool::Arc => {
+            toggle_arc(arcs, first, id);
+            // Arcs chain from the point just clicked
+            let mut s = state.write();
+            s.clear_selection();
+            s.select(id);
+        }
+        Tool::GreatCircle | Tool::SmallCircle => {
+            let action = if tool == Tool::GreatCircle {
+                KeyAction::GreatCircleThroughPoints
+            } else {
+                KeyAction::SmallCircleAroundPole
+            };
+            run_key_action(action, points, arcs, great_circles, small_circles, state);
+            state.write().clear_selection();
+        }
+        _ => {}
+    }
+}
+
+/// Tool buttons across the top of the screen, with a hint for the current tool
+#[component]
+pub fn Toolbar(
+    points: Signal<Vec<Point>>,
+    mut state: Signal<State>,
+    keymap: Signal<Keymap>,
+) -> Element {
+    let current = state.read().tool;
+    let hint = current.hint(&points.read(), &state.read(), &keymap.read());
+    let snap = state.read().snap;
+
+    rsx! {
+        div { class: "toolbar",
+            div { class: "toolbar-buttons",
+                for tool in Tool::ALL {
+                    button {
+                        key: "{tool:?}",
+                        class: if tool == current { "tool-button tool-active" } else { "tool-button" },
+                        title: match keymap.read().binding(tool.action()) {
+                            Some(binding) => format!("{} ({binding})", tool.name()),
+                            None => tool.name().to_string(),
+                        },
+                        onclick: move |_| {
+                            let mut s = state.write();
+                            s.tool = tool;
+                            s.clear_selection();
+                        },
+                        "{tool.name()}"
+                    }
+                }
+                button {
+                    class: if snap { "tool-button tool-option tool-active" } else { "tool-button tool-option" },
+                    title: "Snap new and dragged points onto nearby great circles",
+                    onclick: move |_| state.write().snap = !snap,
+                    "Snap"
+                }
+            }
+            div { class: "tool-hint", "{hint}" }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::*;
+
+const VIEW_ANIMATION_MS: f64 = 450.0;
+const FRAME_MS: u32 = 16;
+
+/// Exponential slow-down of a released spin, per millisecond
+const INERTIA_FRICTION: f64 = 0.004;
+/// Spins slower than this (radians per millisecond) come to rest
+const INERTIA_MIN_SPEED: f64 = 0.00005;
+/// A release later than this after the last drag movement does not spin
+pub const INERTIA_RELEASE_MS: f64 = 60.0;
+
+/// Direction pointing out of the screen towards the viewer
+const VIEW_DIRECTION: Vec3 = [0.0, 0.0, 1.0];
+
+/// Sets the view orientation and updates every point's rotated coordinates
+pub fn set_view(mut points: Signal<Vec<Point>>, mut state: Signal<State>, q: Quaternion) {
+    state.write().quaternion = q;
+    state.write().rotation = q.to_euler_deg();
+    for point in points.write().iter_mut() {
+        point.rotate(q);
+    }
+}
+
+/// Smoothly rotates the view from the current orientation to `target`
+pub fn animate_view_to(points: Signal<Vec<Point>>, mut state: Signal<State>, target: Quaternion) {
+    let start = state.read().quaternion;
+    let animation = state.write().begin_view_animation();
+
+    spawn(async move {
+        let start_time = platform::now_ms();
+        loop {
+            platform::sleep_ms(FRAME_MS).await;
+            // A newer animation or a manual rotation took over
+            if state.read().view_animation != animation {
+                return;
+            }
+            let t = ((platform::now_ms() - start_time) / VIEW_ANIMATION_MS).min(1.0);
+            let eased = t * t * (3.0 - 2.0 * t);
+            set_view(points, state, start.slerp(target, eased));
+            if t >= 1.0 {
+                return;
+            }
+        }
+    });
+}
+
+/// Keeps the sphere spinning about `axis` (in the rotated frame) after a drag is
+/// released, slowing down until it comes to rest
+pub fn spin_with_inertia(
+    points: Signal<Vec<Point>>,
+    mut state: Signal<State>,
+    axis: Vec3,
+    speed: f64,
+) {
+    if speed < INERTIA_MIN_SPEED {
+        return;
+    }
+    let animation = state.write().begin_view_animation();
+
+    spawn(async move {
+        let mut speed = speed;
+        let mut last_frame = platform::now_ms();
+        loop {
+            platform::sleep_ms(FRAME_MS).await;
+            if state.read().view_animation != animation {
+                return;
+            }
+            let now = platform::now_ms();
+            let dt = now - last_frame;
+            last_frame = now;
+            let step = Quaternion::from_axis_angle(axis, speed * dt);
+            let q = step.multiply(state.read().quaternion).normalize();
+            set_view(points, state, q);
+            speed *= (-INERTIA_FRICTION * dt).exp();
+            if speed < INERTIA_MIN_SPEED {
+                return;
+            }
+        }
+    });
+}
+
+/// Animates the view so that `direction` (in the rotated frame) faces the viewer
+fn animate_to_face(points: Signal<Vec<Point>>, state: Signal<State>, direction: Vec3) {
+    let current = state.read().quaternion;
+    let target = Quaternion::from_two_vectors(direction, VIEW_DIRECTION).multiply(current);
+    animate_view_to(points, state, target);
+}
+
+/// Brings the last selected point to the center of the view
+pub fn look_at_selected(points: Signal<Vec<Point>>, state: Signal<State>) {
+    let Some(&id) = state.read().selected().last() else {
+        return;
+    };
+    let direction = points.read()[id].rotated;
+    animate_to_face(points, state, direction);
+}
+
+/// Looks down the pole of the selected great circle, so the circle becomes the limb.
+///
+/// With one point selected the point itself is taken as the pole, with two
+/// selected points the pole of the great circle through them is used.
+pub fn look_at_great_circle_pole(points: Signal<Vec<Point>>, state: Signal<State>) {
+    let direction = match *state.read().selected() {
+        [pole] => points.read()[pole].rotated,
+        [a, b] => {
+            let pa = points.read()[a].rotated;
+            let pb = points.read()[b].rotated;
+            let normal = [
+                pa[1] * pb[2] - pa[2] * pb[1],
+                pa[2] * pb[0] - pa[0] * pb[2],
+                pa[0] * pb[1] - pa[1] * pb[0],
+            ];
+            let mag = (normal[0].powi(2) + normal[1].powi(2) + normal[2].powi(2)).sqrt();
+            if mag < 1e-10 {
+                return;
+            }
+            let normal = [normal[0] / mag, normal[1] / mag, normal[2] / mag];
+            // Of the two poles, pick the one already facing the viewer
+            if normal[2] < 0.0 {
+                [-normal[0], -normal[1], -normal[2]]
+            } else {
+                normal
+            }
+        }
+        _ => return,
+    };
+    animate_to_face(points, state, direction);
+}
+
+/// Animates back to the initial orientation
+pub fn reset_view(points: Signal<Vec<Point>>, state: Signal<State>) {
+    animate_view_to(points, state, Quaternion::identity());
+}
+
+/// Records where the sphere's drawing is on the screen, for mapping the pointer
+/// to the sphere
+pub async fn measure_sphere(svg: &MountedData) {
+    if let Ok(rect) = svg.get_client_rect().await {
+        set_sphere_bounds(
+            rect.origin.x,
+            rect.origin.y,
+            rect.size.width,
+            rect.size.height,
+        );
+    }
+}